@@ -0,0 +1,340 @@
+/// Diagnostics module for the Ruspy language
+///
+/// Turns lexer, parser, type and runtime errors into rustc-style reports that
+/// show the offending source line, carets under the span, an error code,
+/// secondary labels and `help:` notes.
+///
+/// Error codes are grouped by the stage that produced them:
+/// * `E00xx` - lexical errors
+/// * `E01xx` - syntax errors
+/// * `E02xx` - type errors
+/// * `E03xx` - runtime errors
+use crate::lexer::Span;
+use std::env;
+use std::fmt;
+use std::fmt::Write as _;
+use std::io::{self, IsTerminal, Write};
+
+/// A message attached to a region of the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Label {
+    /// Creates a new label at `span`
+    pub fn new(span: Span, message: impl Into<String>) -> Self {
+        Label {
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+/// A fully described problem, ready to be rendered against the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Stable error code such as `E0101`
+    pub code: &'static str,
+    /// Headline describing the problem
+    pub message: String,
+    /// Primary location, underlined with carets
+    pub span: Span,
+    /// Additional locations, underlined with dashes
+    pub secondary: Vec<Label>,
+    /// `help:` notes printed below the snippet
+    pub help: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Creates an error diagnostic at `span`
+    pub fn error(code: &'static str, message: impl Into<String>, span: Span) -> Self {
+        Diagnostic {
+            code,
+            message: message.into(),
+            span,
+            secondary: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    /// Adds a secondary label
+    pub fn with_secondary(mut self, label: Label) -> Self {
+        self.secondary.push(label);
+        self
+    }
+
+    /// Adds a `help:` note
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+}
+
+/// An error located in the source, as produced by the parser, the semantic
+/// checker and the interpreter
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceError {
+    /// Diagnostic code such as `E0101`
+    pub code: &'static str,
+    /// Human readable description of the problem
+    pub message: String,
+    /// Location of the offending code
+    pub span: Span,
+    /// Related locations worth pointing at
+    pub labels: Vec<Label>,
+    /// Suggestion on how to fix the problem
+    pub help: Option<String>,
+}
+
+impl SourceError {
+    /// Creates a new error at `span`
+    pub fn new(code: &'static str, message: impl Into<String>, span: Span) -> Self {
+        SourceError {
+            code,
+            message: message.into(),
+            span,
+            labels: Vec::new(),
+            help: None,
+        }
+    }
+
+    /// Points at a related location
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label::new(span, message));
+        self
+    }
+
+    /// Attaches a suggestion
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+impl From<&SourceError> for Diagnostic {
+    fn from(error: &SourceError) -> Self {
+        let mut diagnostic = Diagnostic::error(error.code, error.message.clone(), error.span);
+        for label in &error.labels {
+            diagnostic = diagnostic.with_secondary(label.clone());
+        }
+        if let Some(help) = &error.help {
+            diagnostic = diagnostic.with_help(help.clone());
+        }
+        diagnostic
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.span)
+    }
+}
+
+/// ANSI escape sequences used when colour output is enabled
+mod style {
+    pub const RESET: &str = "\x1b[0m";
+    pub const BOLD: &str = "\x1b[1m";
+    pub const RED: &str = "\x1b[1;31m";
+    pub const BLUE: &str = "\x1b[1;34m";
+}
+
+/// Renders diagnostics as text, optionally with ANSI colours
+pub struct Renderer<'a> {
+    /// Source the spans point into
+    source: &'a str,
+    /// Name of the file shown in the `-->` line
+    file_name: &'a str,
+    /// Whether to emit ANSI colour codes
+    color: bool,
+}
+
+impl<'a> Renderer<'a> {
+    /// Creates a renderer for `source`, read from `file_name`
+    pub fn new(source: &'a str, file_name: &'a str, color: bool) -> Self {
+        Renderer {
+            source,
+            file_name,
+            color,
+        }
+    }
+
+    /// Wraps `text` in the given style when colour output is enabled
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, style::RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Renders a single diagnostic
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        // Collect the spans to draw, ordered by position
+        let mut labels = vec![(diagnostic.span, None, true)];
+        labels.extend(
+            diagnostic
+                .secondary
+                .iter()
+                .map(|label| (label.span, Some(label.message.as_str()), false)),
+        );
+        labels.sort_by_key(|(span, _, _)| (span.line, span.column));
+
+        let max_line = labels.iter().map(|(span, _, _)| span.line).max().unwrap_or(1);
+        let width = max_line.to_string().len();
+        let gutter = self.paint(style::BLUE, &format!("{} |", " ".repeat(width)));
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}{}",
+            self.paint(style::RED, &format!("error[{}]", diagnostic.code)),
+            self.paint(style::BOLD, &format!(": {}", diagnostic.message)),
+        );
+        let _ = writeln!(
+            out,
+            "{}{} {}:{}:{}",
+            " ".repeat(width),
+            self.paint(style::BLUE, "-->"),
+            self.file_name,
+            diagnostic.span.line,
+            diagnostic.span.column
+        );
+        let _ = writeln!(out, "{}", gutter);
+
+        let mut previous_line = None;
+        for (span, label, primary) in &labels {
+            let (line_text, prefix, length) = self.locate(*span);
+            if previous_line != Some(span.line) {
+                let number = format!("{:>width$} |", span.line, width = width);
+                let _ = writeln!(out, "{} {}", self.paint(style::BLUE, &number), line_text);
+                previous_line = Some(span.line);
+            }
+            let (marker, marker_style) = if *primary {
+                ("^", style::RED)
+            } else {
+                ("-", style::BLUE)
+            };
+            let mut underline = marker.repeat(length);
+            if let Some(label) = label {
+                underline.push(' ');
+                underline.push_str(label);
+            }
+            let _ = writeln!(out, "{} {}{}", gutter, prefix, self.paint(marker_style, &underline));
+        }
+
+        if !diagnostic.help.is_empty() {
+            let _ = writeln!(out, "{}", gutter);
+        }
+        for help in &diagnostic.help {
+            let _ = writeln!(
+                out,
+                "{} {} {}",
+                " ".repeat(width),
+                self.paint(style::BLUE, "="),
+                self.paint(style::BOLD, &format!("help: {}", help))
+            );
+        }
+        out
+    }
+
+    /// Finds the source line containing `span`
+    ///
+    /// # Returns
+    /// * The text of the line, the whitespace needed to reach the span's start
+    ///   column (tabs are kept so the carets line up), and the number of
+    ///   characters to underline on that line (at least one)
+    fn locate(&self, span: Span) -> (&'a str, String, usize) {
+        let start = span.start.min(self.source.len());
+        let line_start = self.source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.source[start..]
+            .find('\n')
+            .map_or(self.source.len(), |i| start + i);
+        let line_text = self.source[line_start..line_end].trim_end_matches('\r');
+
+        let prefix = self.source[line_start..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let end = span.end.clamp(start, line_end);
+        let length = self.source[start..end].chars().count().max(1);
+        (line_text, prefix, length)
+    }
+}
+
+/// Returns true when diagnostics written to `stream` should be coloured
+pub fn use_color(stream: &impl IsTerminal) -> bool {
+    stream.is_terminal() && env::var_os("NO_COLOR").is_none()
+}
+
+/// Renders `diagnostic` against `source` and writes it to stderr
+pub fn emit(diagnostic: &Diagnostic, source: &str, file_name: &str) {
+    let renderer = Renderer::new(source, file_name, use_color(&io::stderr()));
+    let _ = write!(io::stderr(), "{}", renderer.render(diagnostic));
+}
+
+/// Writes the closing `aborting due to ...` line after a batch of errors
+pub fn emit_summary(error_count: usize) {
+    let plural = if error_count == 1 { "" } else { "s" };
+    let renderer = Renderer::new("", "", use_color(&io::stderr()));
+    let _ = writeln!(
+        io::stderr(),
+        "{}{}",
+        renderer.paint(style::RED, "error"),
+        renderer.paint(
+            style::BOLD,
+            &format!(": aborting due to {} previous error{}", error_count, plural)
+        )
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_primary_label() {
+        let source = "x: int = 42;\ny: int = x +;\n";
+        let diagnostic = Diagnostic::error("E0104", "Unexpected token: Semicolon", Span::new(24, 25, 2, 12))
+            .with_help("remove the trailing operator");
+        let rendered = Renderer::new(source, "test.ruspy", false).render(&diagnostic);
+        assert_eq!(
+            rendered,
+            "error[E0104]: Unexpected token: Semicolon\n \
+             --> test.ruspy:2:12\n  \
+             |\n\
+             2 | y: int = x +;\n  \
+             |            ^\n  \
+             |\n  \
+             = help: remove the trailing operator\n"
+        );
+    }
+
+    #[test]
+    fn test_render_secondary_label() {
+        let source = "a = (1 +\n  2;";
+        let diagnostic = Diagnostic::error("E0101", "Expected token RParen, found Semicolon", Span::new(12, 13, 2, 4))
+            .with_secondary(Label::new(Span::new(4, 5, 1, 5), "unclosed delimiter"));
+        let rendered = Renderer::new(source, "test.ruspy", false).render(&diagnostic);
+        assert_eq!(
+            rendered,
+            "error[E0101]: Expected token RParen, found Semicolon\n \
+             --> test.ruspy:2:4\n  \
+             |\n\
+             1 | a = (1 +\n  \
+             |     - unclosed delimiter\n\
+             2 |   2;\n  \
+             |    ^\n"
+        );
+    }
+
+    #[test]
+    fn test_render_color() {
+        let source = "print y;";
+        let diagnostic = Diagnostic::error("E0301", "Undefined variable: y", Span::new(6, 7, 1, 7));
+        let rendered = Renderer::new(source, "test.ruspy", true).render(&diagnostic);
+        assert!(rendered.starts_with("\x1b[1;31merror[E0301]\x1b[0m"));
+        assert!(rendered.contains("\x1b[1;31m^\x1b[0m"));
+    }
+}
@@ -1,30 +1,175 @@
 /// The Interpreter module handles the execution of the Abstract Syntax Tree (AST)
 /// and maintains the state of variables during program execution.
-use crate::lexer::Token;
-use crate::parser::ASTNode;
-use crate::types::RuspyType;
+use crate::diagnostics::SourceError;
+use crate::lexer::{Span, Token};
+use crate::parser::{ASTNode, Function, Node};
+use crate::types::{ArithmeticError, ArithmeticOp, ConversionError, OverflowMode, RuspyType, Type};
+use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::HashMap;
-use log::info;
+use std::io::{self, Write};
+use std::rc::Rc;
+use log::debug;
+
+/// Maximum number of nested function calls before the program is aborted
+///
+/// Each call uses a few tens of kilobytes of native stack in debug builds, so
+/// callers should run the interpreter on a thread with a large enough stack.
+const MAX_CALL_DEPTH: usize = 500;
+
+/// Error raised while executing a program
+pub type RuntimeError = SourceError;
+
+/// Outcome of executing a statement
+///
+/// `break` and `continue` unwind through enclosing blocks as values of this
+/// type rather than as errors, until the innermost loop handles them.
+#[derive(Debug, PartialEq)]
+enum Flow {
+    /// Execution continues with the next statement; carries the statement's value
+    Normal(RuspyType),
+    /// A `break` is leaving the innermost loop
+    Break,
+    /// A `continue` is skipping to the next iteration of the innermost loop
+    Continue,
+    /// A `return` is leaving the current function, with its value if any
+    Return(Option<RuspyType>),
+}
+
+/// Region of the program a scope belongs to
+#[derive(Debug, Clone, PartialEq)]
+enum ScopeKind {
+    /// Top-level variables, alive for the whole program
+    Global,
+    /// Parameters of a call to the named function
+    Function(String),
+    /// Variables declared inside a `{ ... }` block
+    Block,
+}
+
+/// A variable's current value and its type
+struct Variable {
+    value: RuspyType,
+    /// Type fixed by the declaration, or else by the first assignment; every
+    /// later assignment is converted to it
+    ty: Type,
+}
+
+/// A set of bindings whose lifetime ends when the scope is popped
+struct Scope {
+    kind: ScopeKind,
+    variables: HashMap<String, Variable>,
+}
+
+impl Scope {
+    fn new(kind: ScopeKind) -> Self {
+        Scope {
+            kind,
+            variables: HashMap::new(),
+        }
+    }
+
+    /// Binds `name` in this scope, replacing any earlier binding
+    fn bind(&mut self, name: &str, value: RuspyType, ty: Type) {
+        self.variables.insert(name.to_string(), Variable { value, ty });
+    }
+}
+
+/// Writer that can be handed to an interpreter while staying usable by its
+/// creator, so both write to the same sink in the order they were written
+pub struct SharedOutput<W>(Rc<RefCell<W>>);
+
+impl<W> SharedOutput<W> {
+    /// Wraps `output` so it can be shared
+    pub fn new(output: W) -> Self {
+        SharedOutput(Rc::new(RefCell::new(output)))
+    }
+}
+
+impl<W> Clone for SharedOutput<W> {
+    fn clone(&self) -> Self {
+        SharedOutput(Rc::clone(&self.0))
+    }
+}
+
+impl<W: Default> Default for SharedOutput<W> {
+    fn default() -> Self {
+        SharedOutput::new(W::default())
+    }
+}
+
+impl<W: Write> Write for SharedOutput<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.borrow_mut().flush()
+    }
+}
+
+#[cfg(test)]
+impl SharedOutput<Vec<u8>> {
+    /// Returns everything written so far, for inspection in tests
+    pub fn contents(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+}
 
 /// Represents the interpreter state and execution environment
 ///
 /// # Fields
-/// * `variables` - A HashMap storing variable names and their corresponding values
+/// * `scopes` - Stack of variable scopes, innermost last; the first is the global scope
+/// * `functions` - Functions defined so far, by name
+/// * `call_stack` - Index into `scopes` of each active call's function scope, innermost last
+/// * `overflow_mode` - How integer overflow is handled in the code being run
+/// * `output` - Where program output from `print` is written
 pub struct Interpreter {
-    variables: HashMap<String, RuspyType>,
+    scopes: Vec<Scope>,
+    functions: HashMap<String, Rc<Function>>,
+    call_stack: Vec<usize>,
+    overflow_mode: OverflowMode,
+    output: Box<dyn Write>,
 }
 
 impl Interpreter {
     /// Creates a new instance of the Interpreter with an empty variable store
+    /// that prints to stdout
     ///
     /// # Returns
     /// * A new Interpreter instance
     pub fn new() -> Self {
+        Interpreter::with_output(Box::new(io::stdout()))
+    }
+
+    /// Creates a new instance of the Interpreter that prints to `output`
+    ///
+    /// # Arguments
+    /// * `output` - Sink for program output, e.g. a buffer in tests
+    ///
+    /// # Returns
+    /// * A new Interpreter instance
+    pub fn with_output(output: Box<dyn Write>) -> Self {
         Interpreter {
-            variables: HashMap::new(),
+            scopes: vec![Scope::new(ScopeKind::Global)],
+            functions: HashMap::new(),
+            call_stack: Vec::new(),
+            overflow_mode: OverflowMode::Checked,
+            output,
         }
     }
 
+    /// Returns all global variables, sorted by name
+    pub fn variables(&self) -> Vec<(&String, &RuspyType)> {
+        let mut variables: Vec<_> = self.scopes[0]
+            .variables
+            .iter()
+            .map(|(name, variable)| (name, &variable.value))
+            .collect();
+        variables.sort_by(|a, b| a.0.cmp(b.0));
+        variables
+    }
+
     /// Interprets a vector of AST nodes and returns the result of the last expression
     ///
     /// # Arguments
@@ -32,17 +177,119 @@ impl Interpreter {
     ///
     /// # Returns
     /// * The result of the last evaluated expression
-    pub fn interpret(&mut self, nodes: Vec<ASTNode>) -> Result<RuspyType, String> {
+    pub fn interpret(&mut self, nodes: Vec<Node>) -> Result<RuspyType, RuntimeError> {
         let mut last_result = Ok(RuspyType::Int(0));
-        for node in nodes {
-            match self.interpret_node(node) {
-                Ok(result) => last_result = Ok(result),
-                Err(e) => return Err(e),
+        for node in &nodes {
+            match self.execute(node)? {
+                Flow::Normal(result) => last_result = Ok(result),
+                // The parser rejects loop control outside of loops
+                Flow::Break | Flow::Continue => {
+                    return Err(RuntimeError::new("E0305", "Loop control outside of a loop", node.span))
+                }
+                // The parser rejects `return` outside of functions
+                Flow::Return(_) => {
+                    return Err(RuntimeError::new("E0308", "`return` outside of a function", node.span))
+                }
             }
         }
         last_result
     }
 
+    /// Executes a statement, reporting how control leaves it
+    ///
+    /// Statements that affect control flow are handled here; everything else
+    /// is evaluated by interpret_node().
+    ///
+    /// # Arguments
+    /// * `node` - The AST node to execute
+    ///
+    /// # Returns
+    /// * The flow signal produced by the statement
+    fn execute(&mut self, node: &Node) -> Result<Flow, RuntimeError> {
+        match &node.node {
+            // Handle blocks, evaluating to their last statement
+            ASTNode::Block(statements) => self.in_scope(Scope::new(ScopeKind::Block), |this| {
+                let mut last = RuspyType::Int(0);
+                for statement in statements {
+                    match this.execute(statement)? {
+                        Flow::Normal(value) => last = value,
+                        flow => return Ok(flow),
+                    }
+                }
+                Ok(Flow::Normal(last))
+            }),
+
+            // Handle conditionals using the truthiness of the condition
+            ASTNode::If(condition, then_branch, else_branch) => {
+                if self.interpret_node(condition)?.is_truthy() {
+                    self.execute(then_branch)
+                } else if let Some(else_branch) = else_branch {
+                    self.execute(else_branch)
+                } else {
+                    Ok(Flow::Normal(RuspyType::Int(0)))
+                }
+            },
+
+            // Handle loops; the body is re-run while the condition is truthy
+            ASTNode::While(condition, body) => {
+                while self.interpret_node(condition)?.is_truthy() {
+                    match self.execute(body)? {
+                        Flow::Break => break,
+                        Flow::Continue | Flow::Normal(_) => continue,
+                        flow @ Flow::Return(_) => return Ok(flow),
+                    }
+                }
+                Ok(Flow::Normal(RuspyType::Int(0)))
+            },
+
+            // Handle for loops; each iteration binds the loop variable in a
+            // scope of its own, so it doesn't outlive the loop
+            ASTNode::For(name, iterable, body) => {
+                let collection = self.interpret_node(iterable)?;
+                let items = collection.iterate().ok_or_else(|| {
+                    RuntimeError::new(
+                        "E0207",
+                        format!("Cannot iterate over a value of type {}", collection.type_name()),
+                        iterable.span,
+                    )
+                    .with_help("loop over a range, e.g. `range(0, n)`, or a string")
+                })?;
+                for item in items {
+                    let mut scope = Scope::new(ScopeKind::Block);
+                    let ty = item.type_of();
+                    scope.bind(name, item, ty);
+                    match self.in_scope(scope, |this| this.execute(body))? {
+                        Flow::Break => break,
+                        Flow::Continue | Flow::Normal(_) => continue,
+                        flow @ Flow::Return(_) => return Ok(flow),
+                    }
+                }
+                Ok(Flow::Normal(RuspyType::Int(0)))
+            },
+
+            // Handle `wrapping` / `saturating` blocks
+            ASTNode::OverflowBlock(mode, body) => {
+                let enclosing_mode = std::mem::replace(&mut self.overflow_mode, *mode);
+                let flow = self.execute(body);
+                self.overflow_mode = enclosing_mode;
+                flow
+            },
+
+            ASTNode::Break => Ok(Flow::Break),
+            ASTNode::Continue => Ok(Flow::Continue),
+
+            ASTNode::Return(value) => {
+                let value = match value {
+                    Some(expr) => Some(self.interpret_node(expr)?),
+                    None => None,
+                };
+                Ok(Flow::Return(value))
+            },
+
+            _ => self.interpret_node(node).map(Flow::Normal),
+        }
+    }
+
     /// Interprets a single AST node
     ///
     /// # Arguments
@@ -51,77 +298,367 @@ impl Interpreter {
     /// # Returns
     /// * The result of evaluating the node
     ///
-    /// # Panics
+    /// # Errors
     /// * When encountering undefined variables
     /// * When encountering unexpected operators
-    fn interpret_node(&mut self, node: ASTNode) -> Result<RuspyType, String> {
-        match node {
+    fn interpret_node(&mut self, node: &Node) -> Result<RuspyType, RuntimeError> {
+        let span = node.span;
+        match &node.node {
             // Handle literal numbers
-            ASTNode::Number(value) => Ok(RuspyType::Int64(value)),
+            ASTNode::Number(value) => Ok(RuspyType::Int64(*value)),
+
+            // Handle floating-point literals
+            ASTNode::Float(value) => Ok(RuspyType::Float64(*value)),
             
             // Handle string literals
-            ASTNode::StringLiteral(value) => Ok(RuspyType::Str(value)),
+            ASTNode::StringLiteral(value) => Ok(RuspyType::Str(value.clone())),
+            ASTNode::CharLiteral(c) => Ok(RuspyType::Char(*c)),
+
+            // Handle boolean literals
+            ASTNode::Bool(value) => Ok(RuspyType::Bool(*value)),
 
             // Handle variable assignment without type annotation
             ASTNode::VarAssign(name, expr) => {
-                let value = self.interpret_node(*expr)?;
-                self.variables.insert(name, value.clone());
-                Ok(value)
+                let value = self.interpret_node(expr)?;
+                self.assign(name, value, expr.span)
             }
 
             // Handle typed variable assignment
             ASTNode::TypedVarAssign(name, declared_type, expr) => {
-                let value = self.interpret_node(*expr)?;
-                match declared_type {
-                    RuspyType::Str(_) => {
-                        if matches!(
-                            value,
-                            RuspyType::Int(_)
-                                | RuspyType::Int32(_)
-                                | RuspyType::Int64(_)
-                                | RuspyType::Float(_)
-                        ) {
-                            return Err(format!(
-                                "Cannot assign numeric result to string variable '{}'",
-                                name
-                            ));
-                        }
-                    }
-                    _ => {}
+                let value_span = expr.span;
+                let value = self.interpret_node(expr)?;
+                if declared_type.is_string() && value.is_numeric() {
+                    return Err(RuntimeError::new(
+                        "E0202",
+                        format!("Cannot assign numeric result to string variable '{}'", name),
+                        value_span,
+                    )
+                    .with_help("convert the value to a string or declare the variable as a number"));
                 }
-                self.check_type_compatibility(&declared_type, &value)?;
-                self.variables.insert(name, value.clone());
+                let value = self.coerce(declared_type, value, value_span)?;
+                self.declare(name, value.clone(), declared_type.clone());
                 Ok(value)
             }
 
             // Handle variable references
             ASTNode::Identifier(name) => {
-                let value = self.variables.get(&name).cloned();
-                value.ok_or_else(|| format!("Undefined variable: {}", name))
+                let value = self.lookup(name).cloned();
+                value.ok_or_else(|| {
+                    RuntimeError::new(
+                        "E0301",
+                        format!("Undefined variable: {} in {}", name, self.describe_scope()),
+                        span,
+                    )
+                    .with_help(format!("assign a value to '{}' before using it", name))
+                })
+            }
+
+            // Handle function calls
+            ASTNode::Call(name, arguments) => self.call_function(name, arguments, span),
+
+            // Register function definitions; calls look them up by name
+            ASTNode::FunctionDef(function) => {
+                self.functions.insert(function.name.clone(), Rc::clone(function));
+                Ok(RuspyType::Int(0))
             }
 
             // Handle binary operations
             ASTNode::BinaryOp(left, op, right) => {
-                let left_val = self.interpret_node(*left)?;
-                let right_val = self.interpret_node(*right)?;
+                let left_val = self.interpret_node(left)?;
+                let right_val = self.interpret_node(right)?;
+                if let Some(arithmetic_op) = ArithmeticOp::from_token(op) {
+                    return self.arithmetic(arithmetic_op, (&left_val, left.span), (&right_val, right.span), span);
+                }
                 match op {
-                    Token::Plus => Ok(left_val + right_val),
-                    Token::Minus => Ok(left_val - right_val),
-                    Token::Asterisk => Ok(left_val * right_val),
-                    Token::Slash => Ok(left_val / right_val),
-                    _ => Err(format!("Unexpected operator in binary operation")),
+                    Token::EqualEqual
+                    | Token::NotEqual
+                    | Token::Less
+                    | Token::LessEqual
+                    | Token::Greater
+                    | Token::GreaterEqual => self.compare(&left_val, op, &right_val, span),
+                    _ => Err(RuntimeError::new("E0302", "Unexpected operator in binary operation", span)),
                 }
             }
 
+            // Handle `and` / `or`, only evaluating the right side when needed
+            ASTNode::LogicalOp(left, op, right) => {
+                let left_val = self.interpret_node(left)?.is_truthy();
+                let result = match op {
+                    Token::And if !left_val => false,
+                    Token::Or if left_val => true,
+                    Token::And | Token::Or => self.interpret_node(right)?.is_truthy(),
+                    _ => {
+                        return Err(RuntimeError::new(
+                            "E0302",
+                            "Unexpected operator in logical operation",
+                            span,
+                        ))
+                    }
+                };
+                Ok(RuspyType::Bool(result))
+            }
+
+            // Handle unary operations
+            ASTNode::UnaryOp(op, operand) => {
+                let value = self.interpret_node(operand)?;
+                match op {
+                    Token::Not => Ok(RuspyType::Bool(!value.is_truthy())),
+                    _ => Err(RuntimeError::new("E0302", "Unexpected operator in unary operation", span)),
+                }
+            }
+
+            // Handle explicit conversions
+            ASTNode::Cast(target, expr) => {
+                let value = self.interpret_node(expr)?;
+                self.cast(target, &value, expr.span, span)
+            }
+
             // Handle Print statements
             ASTNode::Print(expr) => {
-                let value = self.interpret_node(*expr)?;
-                info!("Output: {}", self.format_value(&value));
+                let value = self.interpret_node(expr)?;
+                let text = self.format_value(&value);
+                debug!("Output: {}", text);
+                writeln!(self.output, "{}", text).map_err(|e| {
+                    RuntimeError::new("E0304", format!("Failed to write output: {}", e), span)
+                })?;
                 Ok(value)
             },
+
+            // Statements that affect control flow are handled by execute()
+            ASTNode::Block(_)
+            | ASTNode::If(..)
+            | ASTNode::While(..)
+            | ASTNode::For(..)
+            | ASTNode::OverflowBlock(..)
+            | ASTNode::Break
+            | ASTNode::Continue
+            | ASTNode::Return(_) => match self.execute(node)? {
+                Flow::Normal(value) => Ok(value),
+                Flow::Break | Flow::Continue => {
+                    Err(RuntimeError::new("E0305", "Loop control outside of a loop", span))
+                }
+                Flow::Return(_) => {
+                    Err(RuntimeError::new("E0308", "`return` outside of a function", span))
+                }
+            },
+
+            // Trees with syntax errors are rejected before they reach the interpreter
+            ASTNode::Error => Err(RuntimeError::new(
+                "E0303",
+                "Cannot execute a statement that failed to parse",
+                span,
+            )),
+        }
+    }
+
+    /// Runs `f` with `scope` pushed, popping it (and anything above it)
+    /// afterwards even if `f` fails
+    fn in_scope<T>(&mut self, scope: Scope, f: impl FnOnce(&mut Self) -> T) -> T {
+        let depth = self.scopes.len();
+        self.scopes.push(scope);
+        let result = f(self);
+        self.scopes.truncate(depth);
+        result
+    }
+
+    /// Index of the outermost scope belonging to the current function, or of
+    /// the global scope outside of functions
+    fn frame_base(&self) -> usize {
+        self.call_stack.last().copied().unwrap_or(0)
+    }
+
+    /// Looks up a variable, walking outward through the current function's
+    /// scopes and then the global scope
+    fn lookup(&self, name: &str) -> Option<&RuspyType> {
+        self.scopes[self.frame_base()..]
+            .iter()
+            .rev()
+            .chain(self.scopes.first())
+            .find_map(|scope| scope.variables.get(name))
+            .map(|variable| &variable.value)
+    }
+
+    /// Assigns to the nearest binding of `name` in the current function (or
+    /// at the top level), creating it in the innermost scope if there is none
+    ///
+    /// A variable keeps the type it was declared with, or else the type of
+    /// its first value: later values are converted to that type, as in a
+    /// typed declaration.
+    ///
+    /// # Errors
+    /// * When the value can't be converted to the variable's type
+    fn assign(&mut self, name: &str, value: RuspyType, span: Span) -> Result<RuspyType, RuntimeError> {
+        let base = self.frame_base();
+        let existing = (base..self.scopes.len())
+            .rev()
+            .find(|&i| self.scopes[i].variables.contains_key(name));
+        let Some(index) = existing else {
+            let ty = value.type_of();
+            self.declare(name, value.clone(), ty);
+            return Ok(value);
+        };
+        let ty = &self.scopes[index].variables[name].ty;
+        let value = self.coerce(ty, value, span).map_err(|error| match error.code {
+            "E0201" => error.with_help(format!(
+                "'{}' has type {}; use a new variable for values of other types",
+                name, ty
+            )),
+            _ => error,
+        })?;
+        if let Some(variable) = self.scopes[index].variables.get_mut(name) {
+            variable.value = value.clone();
+        }
+        Ok(value)
+    }
+
+    /// Binds `name` in the innermost scope, shadowing any outer binding
+    fn declare(&mut self, name: &str, value: RuspyType, ty: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.bind(name, value, ty);
+        }
+    }
+
+    /// Describes the innermost scope for error messages
+    fn describe_scope(&self) -> String {
+        let owner = match &self.scopes[self.frame_base()].kind {
+            ScopeKind::Function(name) => format!("function '{}'", name),
+            ScopeKind::Global | ScopeKind::Block => "the global scope".to_string(),
+        };
+        match self.scopes.last().map(|scope| &scope.kind) {
+            Some(ScopeKind::Block) => format!("a block in {}", owner),
+            _ => owner,
         }
     }
-    
+
+    /// Calls a user-defined function
+    ///
+    /// Arguments are evaluated in the caller's scope, then the body runs in a
+    /// fresh frame holding only the parameters.
+    ///
+    /// # Errors
+    /// * When the function is undefined or called with the wrong arguments
+    /// * When the returned value doesn't match the declared return type
+    /// * When calls nest deeper than MAX_CALL_DEPTH
+    fn call_function(
+        &mut self,
+        name: &str,
+        arguments: &[Node],
+        span: Span,
+    ) -> Result<RuspyType, RuntimeError> {
+        let function = match self.functions.get(name) {
+            Some(function) => Rc::clone(function),
+            None if name == "range" => return self.range(arguments, span),
+            None => {
+                return Err(RuntimeError::new("E0307", format!("Undefined function: {}", name), span)
+                    .with_help(format!("define '{}' with `def` before calling it", name)))
+            }
+        };
+
+        if arguments.len() != function.params.len() {
+            let plural = if function.params.len() == 1 { "" } else { "s" };
+            let error = RuntimeError::new(
+                "E0204",
+                format!(
+                    "Function '{}' takes {} argument{} but {} were given",
+                    name,
+                    function.params.len(),
+                    plural,
+                    arguments.len()
+                ),
+                span,
+            )
+            .with_label(function.body.span, "function body defined here");
+            return Err(error);
+        }
+
+        let mut scope = Scope::new(ScopeKind::Function(name.to_string()));
+        for (param, argument) in function.params.iter().zip(arguments) {
+            let value = self.interpret_node(argument)?;
+            let value = match &param.param_type {
+                Some(param_type) => self.coerce(param_type, value, argument.span)?,
+                None => value,
+            };
+            let ty = param.param_type.clone().unwrap_or_else(|| value.type_of());
+            scope.bind(&param.name, value, ty);
+        }
+
+        if self.call_stack.len() >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::new(
+                "E0306",
+                format!("Maximum call depth of {} exceeded", MAX_CALL_DEPTH),
+                span,
+            )
+            .with_help("check for recursion without a base case"));
+        }
+
+        // The body's overflow mode is the one it was written under, not the caller's
+        self.call_stack.push(self.scopes.len());
+        let caller_mode = std::mem::replace(&mut self.overflow_mode, function.overflow_mode);
+        let flow = self.in_scope(scope, |this| this.execute(&function.body));
+        self.overflow_mode = caller_mode;
+        self.call_stack.pop();
+
+        let value = match flow? {
+            Flow::Return(Some(value)) => value,
+            Flow::Return(None) | Flow::Normal(_) => match &function.return_type {
+                Some(return_type) => {
+                    return Err(RuntimeError::new(
+                        "E0205",
+                        format!(
+                            "Function '{}' must return a value of type {}",
+                            name, return_type
+                        ),
+                        span,
+                    ))
+                }
+                None => RuspyType::Int(0),
+            },
+            // The parser rejects loop control outside of loops
+            Flow::Break | Flow::Continue => {
+                return Err(RuntimeError::new("E0305", "Loop control outside of a loop", span))
+            }
+        };
+        match &function.return_type {
+            Some(return_type) => self.coerce(return_type, value, span),
+            None => Ok(value),
+        }
+    }
+
+    /// Evaluates the built-in `range(start, end[, step])`
+    ///
+    /// # Errors
+    /// * When given the wrong number of arguments or non-integer arguments
+    /// * When the step is zero
+    fn range(&mut self, arguments: &[Node], span: Span) -> Result<RuspyType, RuntimeError> {
+        if !(2..=3).contains(&arguments.len()) {
+            return Err(RuntimeError::new(
+                "E0204",
+                format!("Function 'range' takes 2 or 3 arguments but {} were given", arguments.len()),
+                span,
+            )
+            .with_help("use `range(start, end)` or `range(start, end, step)`"));
+        }
+        let mut bounds = [0, 0, 1];
+        for (bound, argument) in bounds.iter_mut().zip(arguments) {
+            *bound = match self.interpret_node(argument)? {
+                RuspyType::Int(n) | RuspyType::Int32(n) => i64::from(n),
+                RuspyType::Int64(n) => n,
+                other => {
+                    return Err(RuntimeError::new(
+                        "E0206",
+                        format!("range() arguments must be integers, found {}", other.type_name()),
+                        argument.span,
+                    ))
+                }
+            };
+        }
+        let [start, end, step] = bounds;
+        if step == 0 {
+            return Err(RuntimeError::new("E0309", "range() step cannot be zero", arguments[2].span));
+        }
+        Ok(RuspyType::Range(start, end, step))
+    }
+
     // Helper function to format values for printing
     fn format_value(&self, value: &RuspyType) -> String {
         match value {
@@ -132,22 +669,137 @@ impl Interpreter {
             RuspyType::Float32(n) => n.to_string(),
             RuspyType::Float64(n) => n.to_string(),
             RuspyType::Str(s) => s.clone(),
+            RuspyType::Str8(_) | RuspyType::Str32(_) | RuspyType::Str64(_) => value.to_string(),
             RuspyType::Char(c) => c.to_string(),
+            RuspyType::Bool(b) => b.to_string(),
+            RuspyType::Range(..) => value.to_string(),
         }
     }
 
-    fn check_type_compatibility(
+    /// Applies an arithmetic operator under the current overflow mode
+    ///
+    /// # Arguments
+    /// * `left`, `right` - The operand values with the spans they came from
+    /// * `span` - Span of the whole operation
+    fn arithmetic(
         &self,
-        var_type: &RuspyType,
-        value: &RuspyType,
-    ) -> Result<(), String> {
-        if !var_type.is_compatible_with(value) {
-            return Err(format!(
-                "Type mismatch: Cannot assign {:?} to {:?}",
-                value, var_type
+        op: ArithmeticOp,
+        (left, left_span): (&RuspyType, Span),
+        (right, right_span): (&RuspyType, Span),
+        span: Span,
+    ) -> Result<RuspyType, RuntimeError> {
+        left.arithmetic(op, right, self.overflow_mode).map_err(|error| match error {
+            ArithmeticError::Overflow { .. } => {
+                RuntimeError::new("E0310", format!("OverflowError: {}", error), span)
+                    .with_help("use a wider type, or a `wrapping { ... }` or `saturating { ... }` block")
+            }
+            ArithmeticError::CapacityExceeded { .. } => {
+                RuntimeError::new("E0313", error.to_string(), span)
+                    .with_help("use a larger string type such as str64, or str for unbounded strings")
+            }
+            ArithmeticError::DivisionByZero => {
+                RuntimeError::new("E0311", error.to_string(), span)
+                    .with_label(right_span, "this is zero")
+            }
+            ArithmeticError::UnsupportedOperands { left: left_type, right: right_type, .. } => {
+                let error = RuntimeError::new("E0208", error.to_string(), span)
+                    .with_label(left_span, format!("this is {}", left_type))
+                    .with_label(right_span, format!("this is {}", right_type));
+                if left.is_numeric() && right.is_numeric() {
+                    error.with_help(format!(
+                        "{} and {} have no common type; convert one explicitly, e.g. `{}(...)`",
+                        left_type, right_type, right_type
+                    ))
+                } else {
+                    error
+                }
+            }
+        })
+    }
+
+    /// Evaluates a comparison between two values of compatible types
+    fn compare(
+        &self,
+        left: &RuspyType,
+        op: &Token,
+        right: &RuspyType,
+        span: Span,
+    ) -> Result<RuspyType, RuntimeError> {
+        if left.promote_pair(right).is_none() {
+            let error = RuntimeError::new(
+                "E0203",
+                format!("Cannot compare {} with {}", left.type_name(), right.type_name()),
+                span,
+            );
+            return Err(if left.is_numeric() && right.is_numeric() {
+                error.with_help(format!("convert one side explicitly, e.g. `{}(...)`", right.type_name()))
+            } else {
+                error
+            });
+        }
+        // None means the values are unordered (NaN), so only `!=` holds
+        let ordering = left.compare(right);
+        let result = match op {
+            Token::EqualEqual => ordering == Some(Ordering::Equal),
+            Token::NotEqual => ordering != Some(Ordering::Equal),
+            Token::Less => ordering == Some(Ordering::Less),
+            Token::LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Token::Greater => ordering == Some(Ordering::Greater),
+            Token::GreaterEqual => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            _ => unreachable!("compare is only called with comparison operators"),
+        };
+        Ok(RuspyType::Bool(result))
+    }
+
+    /// Converts a value to a declared type
+    ///
+    /// Numbers are promoted where the promotion lattice allows it, and
+    /// otherwise converted within their family (integers to integers, floats
+    /// to floats) as long as the value fits, so `x: int32 = 5` stores an int32.
+    ///
+    /// # Errors
+    /// * E0312 when the value doesn't fit in the declared type
+    /// * E0201 when the value's type can't be converted to the declared type
+    fn coerce(&self, declared: &Type, value: RuspyType, span: Span) -> Result<RuspyType, RuntimeError> {
+        if let Some(promoted) = value.promote(declared) {
+            return Ok(promoted);
+        }
+        if !declared.is_compatible_with(&value.type_of()) {
+            return Err(RuntimeError::new(
+                "E0201",
+                format!("Type mismatch: Cannot assign {} to {}", value.type_name(), declared),
+                span,
             ));
         }
-        Ok(())
+        value.cast(declared).map_err(|error| match error {
+            ConversionError::CapacityExceeded { .. } => RuntimeError::new("E0313", error.to_string(), span)
+                .with_help("use a larger string type such as str64, or str for unbounded strings"),
+            _ => RuntimeError::new("E0312", error.to_string(), span).with_help(format!(
+                "use a wider type than {}, or check the value before assigning it",
+                declared
+            )),
+        })
+    }
+
+    /// Evaluates an explicit conversion such as `int32(x)`
+    ///
+    /// # Errors
+    /// * E0312 when the value doesn't fit in the target type
+    /// * E0313 when a string is longer than the target type's capacity
+    /// * E0209 when there is no conversion between the types
+    fn cast(&self, target: &Type, value: &RuspyType, value_span: Span, span: Span) -> Result<RuspyType, RuntimeError> {
+        value.cast(target).map_err(|error| match error {
+            ConversionError::OutOfRange { .. } => RuntimeError::new("E0312", error.to_string(), span)
+                .with_label(value_span, format!("this is {}", value)),
+            ConversionError::CapacityExceeded { length, .. } => RuntimeError::new("E0313", error.to_string(), span)
+                .with_label(value_span, format!("this is {} bytes long", length)),
+            ConversionError::Unsupported { .. } => RuntimeError::new("E0209", error.to_string(), span)
+                .with_label(value_span, format!("this is {}", value.type_name()))
+                .with_help(
+                    "numbers convert to other number types, strings to other string types \
+                     and chars to and from integer code points",
+                ),
+        })
     }
 }
 
@@ -167,6 +819,563 @@ mod tests {
         assert_eq!(interpreter.interpret(ast), Ok(RuspyType::Int64(8)));
     }
 
+    #[test]
+    fn test_print_writes_to_output() {
+        let input = "
+            x: int64 = 6;
+            print x * 7;
+            print \"done\";
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        let buffer = SharedOutput::<Vec<u8>>::default();
+        let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+        interpreter.interpret(ast).unwrap();
+        assert_eq!(buffer.contents(), "42\ndone\n");
+    }
+
+    #[test]
+    fn test_if_elif_else() {
+        let input = "
+            x = 0;
+            s = \"\";
+            if x { print \"x\"; } elif s { print \"s\"; } else { print \"neither\"; }
+            if x + 1 { print \"one\"; }
+            if 0.0 { print \"float\"; }
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        let buffer = SharedOutput::<Vec<u8>>::default();
+        let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+        interpreter.interpret(ast).unwrap();
+        assert_eq!(buffer.contents(), "neither\none\n");
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let cases = [
+            ("1 < 2;", true),
+            ("x: int32 = 5; x == 5;", true),
+            ("2.5 >= 3.0;", false),
+            ("\"abc\" != \"abd\";", true),
+            ("true == false;", false),
+            ("3 <= 3;", true),
+        ];
+        for (input, expected) in cases {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let ast = parser.parse().unwrap();
+            let mut interpreter = Interpreter::new();
+            assert_eq!(interpreter.interpret(ast), Ok(RuspyType::Bool(expected)), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_incompatible_comparison() {
+        let input = "1 < \"one\";";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        let error = interpreter.interpret(ast).unwrap_err();
+        assert_eq!(error.code, "E0203");
+        assert_eq!(error.message, "Cannot compare int64 with str");
+    }
+
+    #[test]
+    fn test_short_circuit() {
+        // `missing` is undefined, so evaluating it would be an error
+        let input = "
+            a = false and missing;
+            b = true or missing;
+            c = not (1 > 2) and 0;
+            flag: bool = 1 == 1;
+            if flag and not c { print \"ok\"; }
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        let buffer = SharedOutput::<Vec<u8>>::default();
+        let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+        interpreter.interpret(ast).unwrap();
+        assert_eq!(buffer.contents(), "ok\n");
+        let variables: Vec<_> = interpreter
+            .variables()
+            .into_iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        assert_eq!(
+            variables,
+            vec![
+                ("a".to_string(), RuspyType::Bool(false)),
+                ("b".to_string(), RuspyType::Bool(true)),
+                ("c".to_string(), RuspyType::Bool(false)),
+                ("flag".to_string(), RuspyType::Bool(true)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_while_loop() {
+        let input = "
+            i = 0;
+            total = 0;
+            while i < 10 {
+                i = i + 1;
+                if i == 3 { continue; }
+                if i > 6 { break; }
+                total = total + i;
+            }
+            total;
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        // 1 + 2 + 4 + 5 + 6
+        assert_eq!(interpreter.interpret(ast), Ok(RuspyType::Int64(18)));
+    }
+
+    #[test]
+    fn test_nested_loop_break() {
+        let input = "
+            i = 0;
+            while i < 3 {
+                i = i + 1;
+                j = 0;
+                while 1 { j = j + 1; if j == 2 { break; } }
+                print i * 10 + j;
+            }
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        let buffer = SharedOutput::<Vec<u8>>::default();
+        let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+        interpreter.interpret(ast).unwrap();
+        assert_eq!(buffer.contents(), "12\n22\n32\n");
+    }
+
+    #[test]
+    fn test_function_calls() {
+        let input = "
+            scale = 10;
+            def add(a: int64, b: int64) -> int64 { return a + b; }
+            def fact(n) {
+                if n <= 1 { return 1; }
+                return n * fact(n - 1);
+            }
+            def shadow(scale) { x = scale; return x; }
+            print add(fact(5), scale);
+            print shadow(3);
+            scale;
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        let buffer = SharedOutput::<Vec<u8>>::default();
+        let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+        assert_eq!(interpreter.interpret(ast), Ok(RuspyType::Int64(10)));
+        assert_eq!(buffer.contents(), "130\n3\n");
+        // Locals don't leak into the global scope
+        assert!(interpreter.variables().iter().all(|(name, _)| name.as_str() != "x"));
+    }
+
+    #[test]
+    fn test_function_errors() {
+        let cases = [
+            ("missing(1);", "E0307"),
+            ("def f(a) { return a; } f(1, 2);", "E0204"),
+            ("def f(a: str) { return a; } f(1);", "E0201"),
+            ("def f() -> int64 { x = 1; } f();", "E0205"),
+            ("def f() -> str { return 1.5; } f();", "E0201"),
+        ];
+        for (input, code) in cases {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let ast = parser.parse().unwrap();
+            let mut interpreter = Interpreter::new();
+            assert_eq!(interpreter.interpret(ast).unwrap_err().code, code, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_unbounded_recursion() {
+        // Test threads have small stacks, so give this one the room main() does
+        let result = std::thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(|| {
+                let input = "def f(n) { return f(n + 1); } f(0);";
+                let lexer = Lexer::new(input);
+                let mut parser = Parser::new(lexer);
+                let ast = parser.parse().unwrap();
+                Interpreter::new().interpret(ast)
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(result.unwrap_err().code, "E0306");
+    }
+
+    #[test]
+    fn test_block_scoping() {
+        let input = "
+            x: int64 = 1;
+            total = 0;
+            if 1 {
+                x: str = \"inner\";
+                print x;
+                total = 5;
+                temp = 2;
+            }
+            print x;
+            total;
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        let buffer = SharedOutput::<Vec<u8>>::default();
+        let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+        assert_eq!(interpreter.interpret(ast), Ok(RuspyType::Int64(5)));
+        assert_eq!(buffer.contents(), "inner\n1\n");
+        let names: Vec<_> = interpreter.variables().into_iter().map(|(name, _)| name.clone()).collect();
+        assert_eq!(names, vec!["total", "x"]);
+    }
+
+    #[test]
+    fn test_undefined_variable_names_scope() {
+        let cases = [
+            ("if 1 { y = 1; } y;", "Undefined variable: y in the global scope"),
+            ("if 1 { print y; }", "Undefined variable: y in a block in the global scope"),
+            ("def f(a) { return b; } f(1);", "Undefined variable: b in a block in function 'f'"),
+        ];
+        for (input, message) in cases {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let ast = parser.parse().unwrap();
+            let mut interpreter = Interpreter::new();
+            assert_eq!(interpreter.interpret(ast).unwrap_err().message, message, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_for_loops() {
+        let input = "
+            total = 0;
+            for i in range(0, 10) {
+                if i == 2 { continue; }
+                if i == 5 { break; }
+                total = total + i;
+            }
+            for i in range(10, 0, 0 - 3) { print i; }
+            letters = range(0, 0);
+            for c in \"hé\" { print c; }
+            for i in range(0, 3) { i: str = \"shadow\"; }
+            total;
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        let buffer = SharedOutput::<Vec<u8>>::default();
+        let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+        // 0 + 1 + 3 + 4
+        assert_eq!(interpreter.interpret(ast), Ok(RuspyType::Int64(8)));
+        assert_eq!(buffer.contents(), "10\n7\n4\n1\nh\né\n");
+        // The loop variable doesn't outlive the loop
+        let names: Vec<_> = interpreter.variables().into_iter().map(|(name, _)| name.clone()).collect();
+        assert_eq!(names, vec!["letters", "total"]);
+    }
+
+    #[test]
+    fn test_for_loop_errors() {
+        let cases = [
+            ("for i in 5 { }", "E0207"),
+            ("for i in range(0, 5, 0) { }", "E0309"),
+            ("for i in range(0, 1.5) { }", "E0206"),
+            ("for i in range(5) { }", "E0204"),
+        ];
+        for (input, code) in cases {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let ast = parser.parse().unwrap();
+            let mut interpreter = Interpreter::new();
+            assert_eq!(interpreter.interpret(ast).unwrap_err().code, code, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_indented_program() {
+        let input = "// ruspy: indent
+def collatz(n):
+    steps = 0
+    while n != 1:
+        if n - n / 2 * 2 == 0:
+            n = n / 2
+        else:
+            n = 3 * n + 1
+        steps = steps + 1
+    return steps
+
+print collatz(6)
+";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        let buffer = SharedOutput::<Vec<u8>>::default();
+        let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+        interpreter.interpret(ast).unwrap();
+        assert_eq!(buffer.contents(), "8\n");
+    }
+
+    #[test]
+    fn test_integer_overflow() {
+        let input = "
+            big = 9223372036854775807
+            def bump(n) { return n + 1 }
+            wrapping {
+                print big + 1
+                print bump(0)
+            }
+            saturating { print big * 2 }
+            big + 1
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        let buffer = SharedOutput::<Vec<u8>>::default();
+        let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+        let error = interpreter.interpret(ast).unwrap_err();
+        assert_eq!(buffer.contents(), "-9223372036854775808\n1\n9223372036854775807\n");
+        assert_eq!(error.code, "E0310");
+        assert_eq!(error.message, "OverflowError: int64 addition overflowed");
+        assert_eq!(error.span.line, 9);
+    }
+
+    #[test]
+    fn test_arithmetic_errors() {
+        let cases = [
+            ("x = 1 / (2 - 2)", "E0311", "Division by zero"),
+            ("x = 1.5 / 0.0", "E0311", "Division by zero"),
+            ("x = 1 + 2.5", "E0208", "Unsupported operand types for +: int64 and float64"),
+            ("x = \"a\" - \"b\"", "E0208", "Unsupported operand types for -: str and str"),
+        ];
+        for (input, code, message) in cases {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let ast = parser.parse().unwrap();
+            let mut interpreter = Interpreter::new();
+            let error = interpreter.interpret(ast).unwrap_err();
+            assert_eq!((error.code, error.message.as_str()), (code, message), "{}", input);
+            assert_eq!(error.span.start, 4, "{}", input);
+        }
+
+        // The interpreter stays usable after an arithmetic error
+        let lexer = Lexer::new("1 + true");
+        let mut parser = Parser::new(lexer);
+        let mut interpreter = Interpreter::new();
+        let error = interpreter.interpret(parser.parse().unwrap()).unwrap_err();
+        assert_eq!(error.labels[1].message, "this is bool");
+        let lexer = Lexer::new("2 * 3");
+        let mut parser = Parser::new(lexer);
+        assert_eq!(interpreter.interpret(parser.parse().unwrap()), Ok(RuspyType::Int64(6)));
+    }
+
+    #[test]
+    fn test_numeric_promotion() {
+        let input = "
+            small = int32(5) + 10
+            mixed = int(2) + 0.5
+            wide: float64 = float32(0.25)
+            truncated = int64(2.9)
+            less = int32(1) < 2
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let mut interpreter = Interpreter::new();
+        interpreter.interpret(parser.parse().unwrap()).unwrap();
+        let variables: Vec<_> = interpreter
+            .variables()
+            .into_iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        assert_eq!(
+            variables,
+            vec![
+                ("less".to_string(), RuspyType::Bool(true)),
+                ("mixed".to_string(), RuspyType::Float64(2.5)),
+                ("small".to_string(), RuspyType::Int64(15)),
+                ("truncated".to_string(), RuspyType::Int64(2)),
+                ("wide".to_string(), RuspyType::Float64(0.25)),
+            ]
+        );
+
+        let cases = [
+            ("x = int32(3000000000)", "E0312", "Value 3000000000 is out of range for int32"),
+            ("x = str(1)", "E0209", "Cannot convert int64 to str"),
+            ("x = 1 < 1.5", "E0203", "Cannot compare int64 with float64"),
+        ];
+        for (input, code, message) in cases {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let mut interpreter = Interpreter::new();
+            let error = interpreter.interpret(parser.parse().unwrap()).unwrap_err();
+            assert_eq!((error.code, error.message.as_str()), (code, message), "{}", input);
+        }
+
+        // Lossy mixes suggest an explicit cast
+        let lexer = Lexer::new("x = 1 + 2.5");
+        let mut parser = Parser::new(lexer);
+        let mut interpreter = Interpreter::new();
+        let error = interpreter.interpret(parser.parse().unwrap()).unwrap_err();
+        assert!(error.help.unwrap().contains("`float64(...)`"));
+    }
+
+    #[test]
+    fn test_declared_types_persist() {
+        let input = "
+            x: int32 = 5
+            copy = x
+            x = 7
+            total = 10
+            total = x
+            ratio: float32 = 1.5
+            def half(n: int32) -> int32 {
+                n = n / 2
+                return n
+            }
+            h = half(9)
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let mut interpreter = Interpreter::new();
+        interpreter.interpret(parser.parse().unwrap()).unwrap();
+        let variables: Vec<_> = interpreter
+            .variables()
+            .into_iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        assert_eq!(
+            variables,
+            vec![
+                ("copy".to_string(), RuspyType::Int32(5)),
+                ("h".to_string(), RuspyType::Int32(4)),
+                ("ratio".to_string(), RuspyType::Float32(1.5)),
+                ("total".to_string(), RuspyType::Int64(7)),
+                ("x".to_string(), RuspyType::Int32(7)),
+            ]
+        );
+
+        let cases = [
+            ("x: int32 = 3000000000", "E0312", "Value 3000000000 is out of range for int32", 11),
+            ("x: int32 = 1\nx = 3000000000", "E0312", "Value 3000000000 is out of range for int32", 17),
+            ("x: int32 = 1\nx = \"a\"", "E0201", "Type mismatch: Cannot assign str to int32", 17),
+            ("def f(n: int) { n = 1.5 }\nf(1)", "E0201", "Type mismatch: Cannot assign float64 to int", 20),
+            ("x = 1\nx = \"a\"", "E0201", "Type mismatch: Cannot assign str to int64", 10),
+        ];
+        for (input, code, message, start) in cases {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let mut interpreter = Interpreter::new();
+            let error = interpreter.interpret(parser.parse().unwrap()).unwrap_err();
+            assert_eq!((error.code, error.message.as_str()), (code, message), "{}", input);
+            assert_eq!(error.span.start, start, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_chars() {
+        let input = "
+            c: char = 'a'
+            code = int64(c)
+            next = char(code + 1)
+            word = \"ab\" + next + '!'
+            tag: str8 = '#' + \"rs\"
+            ordered = c < next and next == 'b'
+            for letter in \"hé\" { if letter >= 'h' { print letter } }
+            print '\\u{e9}'
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let buffer = SharedOutput::<Vec<u8>>::default();
+        let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+        interpreter.interpret(parser.parse().unwrap()).unwrap();
+        assert_eq!(buffer.contents(), "h\né\né\n");
+        let types: Vec<_> = interpreter
+            .variables()
+            .into_iter()
+            .map(|(name, value)| format!("{}: {} = {}", name, value.type_name(), value))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                "c: char = a",
+                "code: int64 = 97",
+                "next: char = b",
+                "ordered: bool = true",
+                "tag: str8 = #rs",
+                "word: str = abb!",
+            ]
+        );
+
+        let cases = [
+            ("c: char = \"a\"", "E0201", "Type mismatch: Cannot assign str to char"),
+            ("x = 'a' + 1", "E0208", "Unsupported operand types for +: char and int64"),
+            ("x = 'a' < \"a\"", "E0203", "Cannot compare char with str"),
+            ("x = char(1114112)", "E0312", "Value 1114112 is out of range for char"),
+            ("s: str8 = \"12345678\"\ns = s + '9'", "E0313", "str8 concatenation produced 9 bytes, but str8 holds at most 8"),
+        ];
+        for (input, code, message) in cases {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let mut interpreter = Interpreter::new();
+            let error = interpreter.interpret(parser.parse().unwrap()).unwrap_err();
+            assert_eq!((error.code, error.message.as_str()), (code, message), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_fixed_capacity_strings() {
+        let input = "
+            name: str8 = \"ruspy\"
+            label: str32 = name + \"!\"
+            short: str8 = \"ab\"
+            pair = short + short
+            name = \"interp\"
+            print label
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let buffer = SharedOutput::<Vec<u8>>::default();
+        let mut interpreter = Interpreter::with_output(Box::new(buffer.clone()));
+        interpreter.interpret(parser.parse().unwrap()).unwrap();
+        assert_eq!(buffer.contents(), "ruspy!\n");
+        let types: Vec<_> = interpreter
+            .variables()
+            .into_iter()
+            .map(|(name, value)| format!("{}: {} = {}", name, value.type_name(), value))
+            .collect();
+        assert_eq!(
+            types,
+            vec!["label: str32 = ruspy!", "name: str8 = interp", "pair: str8 = abab", "short: str8 = ab"]
+        );
+
+        let cases = [
+            ("s: str8 = \"far too long\"", "E0313", "A string of 12 bytes doesn't fit in str8, which holds at most 8"),
+            ("s: str8 = \"abc\"\ns = \"abcdefghi\"", "E0313", "A string of 9 bytes doesn't fit in str8, which holds at most 8"),
+            ("a: str8 = \"abcde\"\nb = a + a", "E0313", "str8 concatenation produced 10 bytes, but str8 holds at most 8"),
+            ("s: str8 = 5", "E0202", "Cannot assign numeric result to string variable 's'"),
+        ];
+        for (input, code, message) in cases {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let mut interpreter = Interpreter::new();
+            let error = interpreter.interpret(parser.parse().unwrap()).unwrap_err();
+            assert_eq!((error.code, error.message.as_str()), (code, message), "{}", input);
+        }
+    }
+
     #[test]
     fn test_variables() {
         let input = "
@@ -206,6 +1415,20 @@ mod tests {
         assert_eq!(interpreter.interpret(ast), Ok(RuspyType::Int64(84)));
     }
 
+    #[test]
+    fn test_float_variables() {
+        let input = "
+            x: float = 3.5;
+            y: float64 = x * 2.0;
+            y;
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        assert_eq!(interpreter.interpret(ast), Ok(RuspyType::Float64(7.0)));
+    }
+
     #[test]
     fn test_type_mismatch() {
         let input = "x: int = 42;
@@ -224,6 +1447,8 @@ mod tests {
         let mut parser = Parser::new(lexer);
         let ast = parser.parse().unwrap();
         let mut interpreter = Interpreter::new();
-        assert!(interpreter.interpret(ast).is_err());
+        let error = interpreter.interpret(ast).unwrap_err();
+        assert_eq!(error.message, "Undefined variable: b in the global scope");
+        assert_eq!(error.span, Span::new(11, 12, 1, 12));
     }
 }
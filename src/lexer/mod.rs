@@ -1,12 +1,164 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::Path;
 use std::str::Chars;
 
+/// First-line comment that switches a file to indentation-based blocks
+pub const INDENT_PRAGMA: &str = "// ruspy: indent";
+
+/// File extension of sources that use indentation-based blocks
+pub const INDENT_EXTENSION: &str = "rpy";
+
+/// Opens and closes string literals that may span several lines
+const TRIPLE_QUOTE: &str = "\"\"\"";
+
+/// A region of the source text
+///
+/// Stores the byte range of the region together with the line and column
+/// (both 1-based) at which it starts, so errors can point at exact locations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    /// Byte offset of the first character
+    pub start: usize,
+    /// Byte offset one past the last character
+    pub end: usize,
+    /// Line on which the span starts
+    pub line: usize,
+    /// Column on which the span starts
+    pub column: usize,
+}
+
+impl Span {
+    /// Creates a new span
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Span { start, end, line, column }
+    }
+
+    /// Returns a span covering both `self` and `other`
+    ///
+    /// `other` is expected to end at or after `self`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end.max(self.end),
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// A value paired with the source span it was produced from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    /// Wraps `node` with its span
+    pub fn new(node: T, span: Span) -> Self {
+        Spanned { node, span }
+    }
+}
+
+/// The different ways tokenizing can fail
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexErrorKind {
+    /// A character that cannot start any token
+    UnexpectedChar(char),
+    /// A string literal that reaches the end of its line, or a triple-quoted
+    /// one the end of input, without closing quotes
+    UnterminatedString,
+    /// A char literal that reaches the end of its line without a closing quote
+    UnterminatedChar,
+    /// A char literal holding no characters or more than one
+    InvalidCharLiteral,
+    /// An unknown escape sequence, or a `\u{...}` that names no character
+    InvalidEscape(String),
+    /// A numeric literal that does not fit in the target type
+    NumericOverflow(String),
+    /// Indentation that mixes tabs and spaces, within a line or across lines
+    MixedIndentation,
+    /// A dedent to a width that matches no enclosing indentation level
+    InconsistentDedent,
+}
+
+/// Error produced when the input cannot be tokenized
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    /// What went wrong
+    pub kind: LexErrorKind,
+    /// Location of the offending text
+    pub span: Span,
+}
+
+impl LexError {
+    /// Creates a new lexer error at `span`
+    pub fn new(kind: LexErrorKind, span: Span) -> Self {
+        LexError { kind, span }
+    }
+}
+
+impl LexErrorKind {
+    /// Returns the diagnostic code for this kind of error
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexErrorKind::UnexpectedChar(_) => "E0001",
+            LexErrorKind::UnterminatedString => "E0002",
+            LexErrorKind::NumericOverflow(_) => "E0003",
+            LexErrorKind::MixedIndentation => "E0004",
+            LexErrorKind::InconsistentDedent => "E0005",
+            LexErrorKind::UnterminatedChar => "E0006",
+            LexErrorKind::InvalidCharLiteral => "E0007",
+            LexErrorKind::InvalidEscape(_) => "E0008",
+        }
+    }
+}
+
+impl fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexErrorKind::UnexpectedChar(c) => write!(f, "Unexpected character: {:?}", c),
+            LexErrorKind::UnterminatedString => write!(f, "Unterminated string literal"),
+            LexErrorKind::UnterminatedChar => write!(f, "Unterminated char literal"),
+            LexErrorKind::InvalidCharLiteral => {
+                write!(f, "Char literal must contain exactly one character")
+            }
+            LexErrorKind::InvalidEscape(escape) => write!(f, "Invalid escape sequence: {}", escape),
+            LexErrorKind::NumericOverflow(literal) => {
+                write!(f, "Numeric literal out of range: {}", literal)
+            }
+            LexErrorKind::MixedIndentation => {
+                write!(f, "Inconsistent use of tabs and spaces in indentation")
+            }
+            LexErrorKind::InconsistentDedent => {
+                write!(f, "Unindent does not match any outer indentation level")
+            }
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.span)
+    }
+}
+
 /// Token enumeration representing different lexical elements in the Ruspy language
 /// Each variant corresponds to a specific type of token that can be recognized by the lexer
 #[derive(Debug, PartialEq, Clone)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Token {
     // identifiers
     Identifier(String),
     Number(i64),
+    Float(f64),
 
     // operators
     Plus,
@@ -14,9 +166,28 @@ pub enum Token {
     Asterisk,
     Slash,
 
+    // comparison operators
+    EqualEqual,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+
+    // logical operators
+    And,
+    Or,
+    Not,
+
     // brackets
     LParen,
     RParen,
+    LBrace,
+    RBrace,
+
+    // separators
+    Comma,
+    Arrow,
 
     // assignment
     Assign,
@@ -26,6 +197,15 @@ pub enum Token {
     Colon,
     EOF,
 
+    // Layout tokens; INDENT and DEDENT are only produced in indentation mode
+    Newline,
+    Indent,
+    Dedent,
+
+    // Stands in for text the lexer rejected; never produced by the lexer
+    // itself, only by a parser skipping past a lexer error
+    Invalid,
+
     // Variable type tokens
     TypeInt,
     TypeInt32,
@@ -38,12 +218,36 @@ pub enum Token {
     TypeStr8,
     TypeStr32,
     TypeStr64,
+    TypeBool,
 
     // Add print keyword
     Print,
 
+    // Control flow keywords
+    If,
+    Elif,
+    Else,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
+
+    // Function keywords
+    Def,
+    Return,
+
+    // Overflow mode keywords
+    Wrapping,
+    Saturating,
+
     // Add string literals
     StringLiteral(String),
+    CharLiteral(char),
+
+    // Boolean literals
+    True,
+    False,
 }
 
 /// Lexer struct responsible for tokenizing input source code
@@ -56,8 +260,30 @@ pub struct Lexer<'a> {
     current_char: Option<char>,
     /// Source text for cloning purposes
     source: &'a str,
-    /// Current position in the input
+    /// Byte offset of `current_char` in the input
     position: usize,
+    /// Line of `current_char` (1-based)
+    line: usize,
+    /// Column of `current_char` (1-based)
+    column: usize,
+    /// Whether blocks are delimited by indentation rather than braces
+    indentation: bool,
+    /// Widths of the open indentation levels, outermost (0) first
+    indent_stack: Vec<usize>,
+    /// Character the file indents with, once known
+    indent_char: Option<char>,
+    /// Tokens produced ahead of time, such as several DEDENTs at once
+    pending: VecDeque<Spanned<Token>>,
+    /// Whether the next token is the first on its line
+    at_line_start: bool,
+    /// Whether a token has been produced on the current line
+    line_has_tokens: bool,
+    /// Whether the last token leaves the line unfinished, e.g. a trailing `+`
+    line_continues: bool,
+    /// Number of unclosed `(`; line breaks inside parentheses are ignored
+    paren_depth: usize,
+    /// Number of unclosed `{`; indentation inside braces is ignored
+    brace_depth: usize,
 }
 
 impl<'a> Lexer<'a> {
@@ -67,110 +293,283 @@ impl<'a> Lexer<'a> {
     /// * `input` - The source code string to be tokenized
     ///
     /// # Returns
-    /// * A new Lexer instance initialized with the input
+    /// * A new Lexer instance initialized with the input; it uses
+    ///   indentation-based blocks if the first line is [`INDENT_PRAGMA`]
     pub fn new(input: &'a str) -> Self {
+        let indentation = input.lines().next().map(str::trim) == Some(INDENT_PRAGMA);
         let mut lexer = Lexer {
             input: input.chars(),
             current_char: None,
             source: input,
             position: 0,
+            line: 1,
+            column: 0,
+            indentation,
+            indent_stack: vec![0],
+            indent_char: None,
+            pending: VecDeque::new(),
+            at_line_start: true,
+            line_has_tokens: false,
+            line_continues: false,
+            paren_depth: 0,
+            brace_depth: 0,
         };
         lexer.advance();
         lexer
     }
 
+    /// Creates a Lexer that emits NEWLINE, INDENT and DEDENT tokens, so
+    /// blocks can be written as a `:` followed by an indented body
+    pub fn with_indentation(input: &'a str) -> Self {
+        let mut lexer = Lexer::new(input);
+        lexer.indentation = true;
+        lexer
+    }
+
+    /// Creates a Lexer for the contents of the file at `path`, using
+    /// indentation mode for files with the [`INDENT_EXTENSION`] extension
+    pub fn for_file(input: &'a str, path: &str) -> Self {
+        let extension = Path::new(path).extension().and_then(|e| e.to_str());
+        if extension == Some(INDENT_EXTENSION) {
+            Lexer::with_indentation(input)
+        } else {
+            Lexer::new(input)
+        }
+    }
+
     /// Advances the lexer to the next character in the input stream
     ///
     /// This method updates the current_char field with the next character
-    /// or None if we've reached the end of input
+    /// or None if we've reached the end of input, keeping the byte position,
+    /// line and column in sync.
     fn advance(&mut self) {
+        if let Some(c) = self.current_char {
+            self.position += c.len_utf8();
+        }
+        if self.current_char == Some('\n') {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
         self.current_char = self.input.next();
     }
 
+    /// Returns the character after `current_char` without consuming anything
+    fn peek(&self) -> Option<char> {
+        self.input.clone().next()
+    }
+
+    /// Returns a zero-width span at the current position
+    fn mark(&self) -> Span {
+        Span::new(self.position, self.position, self.line, self.column)
+    }
+
+    /// Returns a span from `start` up to the current position
+    fn span_from(&self, start: Span) -> Span {
+        Span::new(start.start, self.position, start.line, start.column)
+    }
+
     /// Returns the next token from the input stream
     ///
     /// # Returns
     /// * The next Token in the sequence
     ///
-    /// # Panics
-    /// * When encountering unexpected characters in the input
-    pub fn get_next_token(&mut self) -> Token {
-        // Skip whitespace and process the next meaningful character
-        while let Some(c) = self.current_char {
-            if c.is_whitespace() {
-                self.advance();
+    /// # Errors
+    /// * When the input at the current position cannot be tokenized
+    pub fn get_next_token(&mut self) -> Result<Token, LexError> {
+        self.next_token().map(|token| token.node)
+    }
+
+    /// Returns the next token from the input stream together with its span
+    ///
+    /// # Returns
+    /// * The next Token in the sequence and the source region it covers
+    ///
+    /// # Errors
+    /// * When encountering unexpected characters, unterminated strings or
+    ///   numeric literals that overflow
+    pub fn next_token(&mut self) -> Result<Spanned<Token>, LexError> {
+        // Skip whitespace and comments, then process the next meaningful character
+        loop {
+            if let Some(token) = self.pending.pop_front() {
+                return Ok(token);
+            }
+            if self.indentation && self.at_line_start && self.paren_depth == 0 {
+                self.at_line_start = false;
+                self.indentation_tokens()?;
                 continue;
             }
 
-            // Handle different character types
-            if c.is_alphabetic() {
-                return self.identifier();
+            let Some(c) = self.current_char else { break };
+
+            // A line break ends the line's statement, unless the line is
+            // unfinished; the next line then continues it, whatever its
+            // indentation
+            if c == '\n' && self.paren_depth == 0 {
+                let start = self.mark();
+                self.advance();
+                if self.line_continues {
+                    continue;
+                }
+                self.at_line_start = true;
+                // Blank lines don't produce a NEWLINE
+                if std::mem::take(&mut self.line_has_tokens) {
+                    return Ok(Spanned::new(Token::Newline, self.span_from(start)));
+                }
+                continue;
             }
 
-            if c.is_digit(10) {
-                return self.number();
+            if c.is_whitespace() {
+                self.advance();
+                continue;
             }
 
-            // Match single-character tokens
-            match c {
-                '+' => {
-                    self.advance();
-                    return Token::Plus;
-                }
-                '-' => {
-                    self.advance();
-                    return Token::Minus;
-                }
-                '*' => {
+            // Skip line comments (// until end of line or end of file)
+            if c == '/' && self.peek() == Some('/') {
+                while self.current_char.is_some_and(|c| c != '\n') {
                     self.advance();
-                    return Token::Asterisk;
                 }
-                '/' => {
-                    self.advance();
-                    // Check if it's a comment (// for line comment)
-                    if let Some('/') = self.current_char {
-                        self.advance(); // consume the second '/'
-                        // Skip everything until end of line or end of file
-                        while let Some(c) = self.current_char {
-                            if c == '\n' {
-                                self.advance(); // consume the newline
-                                break;
-                            }
-                            self.advance(); // consume the comment character
-                        }
-                        // After skipping the comment, get the next token
-                        return self.get_next_token();
-                    } else {
-                        return Token::Slash;
-                    }
-                }
-                '(' => {
-                    self.advance();
-                    return Token::LParen;
-                }
-                ')' => {
-                    self.advance();
-                    return Token::RParen;
-                }
-                '=' => {
-                    self.advance();
-                    return Token::Assign;
-                }
-                ';' => {
-                    self.advance();
-                    return Token::Semicolon;
-                }
-                ':' => {
-                    self.advance();
-                    return Token::Colon;
-                }
-                '"' => {
-                    return self.string_literal();
-                }
-                _ => panic!("Unexpected character: {}", c),
+                continue;
+            }
+
+            let start = self.mark();
+            let token = self.scan_token(c, start)?;
+            match token {
+                Token::LParen => self.paren_depth += 1,
+                Token::RParen => self.paren_depth = self.paren_depth.saturating_sub(1),
+                Token::LBrace => self.brace_depth += 1,
+                Token::RBrace => self.brace_depth = self.brace_depth.saturating_sub(1),
+                _ => {}
+            }
+            self.line_has_tokens = true;
+            self.line_continues = continues_line(&token);
+            return Ok(Spanned::new(token, self.span_from(start)));
+        }
+
+        // Close the last line and every open indentation level
+        if self.indentation {
+            if std::mem::take(&mut self.line_has_tokens) {
+                return Ok(Spanned::new(Token::Newline, self.mark()));
+            }
+            if self.indent_stack.len() > 1 {
+                self.indent_stack.pop();
+                return Ok(Spanned::new(Token::Dedent, self.mark()));
             }
         }
-        Token::EOF
+        Ok(Spanned::new(Token::EOF, self.mark()))
+    }
+
+    /// Reads the leading whitespace of a line and queues the INDENT or
+    /// DEDENT tokens it implies
+    ///
+    /// Blank and comment-only lines, and lines inside braces, leave the
+    /// indentation unchanged.
+    ///
+    /// # Errors
+    /// * When the indentation mixes tabs and spaces
+    /// * When a dedent doesn't return to an enclosing indentation level
+    fn indentation_tokens(&mut self) -> Result<(), LexError> {
+        let start = self.mark();
+        let mut width = 0;
+        let mut mixed = false;
+        while let Some(c @ (' ' | '\t')) = self.current_char {
+            mixed |= *self.indent_char.get_or_insert(c) != c;
+            width += 1;
+            self.advance();
+        }
+        let span = self.span_from(start);
+
+        let blank = match self.current_char {
+            None | Some('\n' | '\r') => true,
+            Some('/') => self.peek() == Some('/'),
+            _ => false,
+        };
+        if blank || self.brace_depth > 0 {
+            return Ok(());
+        }
+        if mixed {
+            return Err(LexError::new(LexErrorKind::MixedIndentation, span));
+        }
+
+        let current = self.indent_stack.last().copied().unwrap_or(0);
+        if width > current {
+            self.indent_stack.push(width);
+            self.pending.push_back(Spanned::new(Token::Indent, span));
+        } else if width < current {
+            while self.indent_stack.last().is_some_and(|&level| level > width) {
+                self.indent_stack.pop();
+                self.pending.push_back(Spanned::new(Token::Dedent, span));
+            }
+            if self.indent_stack.last() != Some(&width) {
+                return Err(LexError::new(LexErrorKind::InconsistentDedent, span));
+            }
+        }
+        Ok(())
+    }
+
+    /// Scans a single token starting at the character `c`, located at `start`
+    fn scan_token(&mut self, c: char, start: Span) -> Result<Token, LexError> {
+        // Raw strings, as in `r"C:\dir"`
+        if c == 'r' && self.peek() == Some('"') {
+            self.advance(); // Skip the prefix
+            return self.string_literal(start, true);
+        }
+
+        // Handle different character types
+        if c.is_alphabetic() {
+            return Ok(self.identifier());
+        }
+
+        // Numbers may also start with a dot, as in `.5`
+        let leading_dot = c == '.' && self.peek().is_some_and(|next| next.is_ascii_digit());
+        if c.is_ascii_digit() || leading_dot {
+            return self.number(start);
+        }
+
+        // Match two-character operators
+        let two_char_token = match (c, self.peek()) {
+            ('=', Some('=')) => Some(Token::EqualEqual),
+            ('!', Some('=')) => Some(Token::NotEqual),
+            ('<', Some('=')) => Some(Token::LessEqual),
+            ('>', Some('=')) => Some(Token::GreaterEqual),
+            ('-', Some('>')) => Some(Token::Arrow),
+            _ => None,
+        };
+        if let Some(token) = two_char_token {
+            self.advance();
+            self.advance();
+            return Ok(token);
+        }
+
+        // Match single-character tokens
+        let token = match c {
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Asterisk,
+            '/' => Token::Slash,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            ',' => Token::Comma,
+            '=' => Token::Assign,
+            '<' => Token::Less,
+            '>' => Token::Greater,
+            ';' => Token::Semicolon,
+            ':' => Token::Colon,
+            '"' => return self.string_literal(start, false),
+            '\'' => return self.char_literal(start),
+            _ => {
+                self.advance();
+                return Err(LexError::new(
+                    LexErrorKind::UnexpectedChar(c),
+                    self.span_from(start),
+                ));
+            }
+        };
+        self.advance();
+        Ok(token)
     }
 
     /// Processes and returns an identifier or keyword token
@@ -202,57 +601,283 @@ impl<'a> Lexer<'a> {
             "str8" => Token::TypeStr8,
             "str32" => Token::TypeStr32,
             "str64" => Token::TypeStr64,
+            "bool" => Token::TypeBool,
             "print" => Token::Print,
+            "if" => Token::If,
+            "elif" => Token::Elif,
+            "else" => Token::Else,
+            "while" => Token::While,
+            "break" => Token::Break,
+            "continue" => Token::Continue,
+            "for" => Token::For,
+            "in" => Token::In,
+            "def" => Token::Def,
+            "return" => Token::Return,
+            "wrapping" => Token::Wrapping,
+            "saturating" => Token::Saturating,
+            "and" => Token::And,
+            "or" => Token::Or,
+            "not" => Token::Not,
+            "true" => Token::True,
+            "false" => Token::False,
             _ => Token::Identifier(result),
         }
     }
 
-    /// Processes and returns a number token
-    ///
-    /// # Returns
-    /// * A Number Token containing the parsed value
-    ///
-    /// # Panics
-    /// * When the number cannot be parsed as an i64
-    fn number(&mut self) -> Token {
-        let mut result = String::new();
-        // Collect all consecutive digits
+    /// Consumes all consecutive ASCII digits
+    fn digits(&mut self) {
         while let Some(c) = self.current_char {
-            if c.is_digit(10) {
-                result.push(c);
+            if c.is_ascii_digit() {
                 self.advance();
             } else {
                 break;
             }
         }
-        Token::Number(result.parse::<i64>().unwrap())
+    }
+
+    /// Processes and returns a number token
+    ///
+    /// Integers become `Number` tokens. A fractional part (`3.14`, `.5`) or an
+    /// exponent (`1e-3`, `2.5E10`) makes the literal a `Float` token instead.
+    ///
+    /// # Returns
+    /// * A Number or Float Token containing the parsed value
+    ///
+    /// # Errors
+    /// * When an integer does not fit in an i64 or a float is not finite
+    fn number(&mut self, start: Span) -> Result<Token, LexError> {
+        let mut is_float = false;
+        self.digits();
+
+        // Fractional part
+        if self.current_char == Some('.') && self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            is_float = true;
+            self.advance(); // consume the '.'
+            self.digits();
+        }
+
+        // Exponent, only when digits follow so `2e` stays a number and an identifier
+        if matches!(self.current_char, Some('e' | 'E')) {
+            let mut lookahead = self.input.clone();
+            let has_exponent = match lookahead.next() {
+                Some('+' | '-') => lookahead.next().is_some_and(|c| c.is_ascii_digit()),
+                Some(c) => c.is_ascii_digit(),
+                None => false,
+            };
+            if has_exponent {
+                is_float = true;
+                self.advance(); // consume the 'e'
+                if matches!(self.current_char, Some('+' | '-')) {
+                    self.advance();
+                }
+                self.digits();
+            }
+        }
+
+        let literal = &self.source[start.start..self.position];
+        let token = if is_float {
+            literal
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .map(Token::Float)
+        } else {
+            literal.parse::<i64>().ok().map(Token::Number)
+        };
+        token.ok_or_else(|| {
+            LexError::new(
+                LexErrorKind::NumericOverflow(literal.to_string()),
+                self.span_from(start),
+            )
+        })
     }
 
     /// Processes and returns a string literal token
     ///
+    /// A literal opened with `"""` may span lines and ends at the next `"""`;
+    /// any other ends at the next `"` on the same line. Escape sequences are
+    /// replaced by the characters they stand for, except in raw strings.
+    ///
+    /// # Arguments
+    /// * `start` - Position of the literal, including any `r` prefix
+    /// * `raw` - Whether backslashes are kept as written
+    ///
     /// # Returns
     /// * A StringLiteral Token containing the parsed string
     ///
-    /// # Panics
+    /// # Errors
     /// * When the string is not properly terminated
-    fn string_literal(&mut self) -> Token {
-        self.advance(); // Skip the opening quote
+    /// * When it contains an invalid escape sequence
+    fn string_literal(&mut self, start: Span, raw: bool) -> Result<Token, LexError> {
+        let triple = self.source[self.position..].starts_with(TRIPLE_QUOTE);
+        let quotes = if triple { TRIPLE_QUOTE.len() } else { 1 };
+        for _ in 0..quotes {
+            self.advance(); // Skip the opening quotes
+        }
         let mut result = String::new();
+        let mut escape_error = None;
 
-        // Collect characters until closing quote
-        while let Some(c) = self.current_char {
-            if c == '"' {
-                self.advance(); // Skip the closing quote
-                return Token::StringLiteral(result);
+        // Collect characters until the closing quotes, scanning past invalid escapes
+        loop {
+            match self.current_char {
+                None => break,
+                Some('\n') if !triple => break,
+                Some('"') if !triple || self.source[self.position..].starts_with(TRIPLE_QUOTE) => {
+                    for _ in 0..quotes {
+                        self.advance(); // Skip the closing quotes
+                    }
+                    return match escape_error {
+                        Some(error) => Err(error),
+                        None => Ok(Token::StringLiteral(result)),
+                    };
+                }
+                Some('\\') if !raw => match self.escape() {
+                    Ok(c) => result.push(c),
+                    Err(e) => escape_error = escape_error.or(Some(e)),
+                },
+                Some(c) => {
+                    result.push(c);
+                    self.advance();
+                }
             }
-            result.push(c);
+        }
+
+        Err(LexError::new(
+            LexErrorKind::UnterminatedString,
+            self.span_from(start),
+        ))
+    }
+
+    /// Processes and returns a char literal token, such as `'a'` or `'\n'`
+    ///
+    /// # Returns
+    /// * A CharLiteral Token containing the character
+    ///
+    /// # Errors
+    /// * When the literal isn't closed on the same line
+    /// * When it holds no characters or more than one
+    /// * When it contains an invalid escape sequence
+    fn char_literal(&mut self, start: Span) -> Result<Token, LexError> {
+        self.advance(); // Skip the opening quote
+        let mut chars = Vec::new();
+        let mut escape_error = None;
+
+        // Scan up to the closing quote even after an error, so lexing resumes after the literal
+        loop {
+            match self.current_char {
+                None | Some('\n') => {
+                    return Err(LexError::new(
+                        LexErrorKind::UnterminatedChar,
+                        self.span_from(start),
+                    ));
+                }
+                Some('\'') => break,
+                Some('\\') => match self.escape() {
+                    Ok(c) => chars.push(c),
+                    Err(e) => escape_error = escape_error.or(Some(e)),
+                },
+                Some(c) => {
+                    chars.push(c);
+                    self.advance();
+                }
+            }
+        }
+        self.advance(); // Skip the closing quote
+
+        if let Some(error) = escape_error {
+            return Err(error);
+        }
+        match chars[..] {
+            [c] => Ok(Token::CharLiteral(c)),
+            _ => Err(LexError::new(
+                LexErrorKind::InvalidCharLiteral,
+                self.span_from(start),
+            )),
+        }
+    }
+
+    /// Processes an escape sequence starting at the backslash under
+    /// `current_char`
+    ///
+    /// Supports `\n`, `\t`, `\r`, `\0`, `\\`, `\'`, `\"` and `\u{...}` with
+    /// 1 to 6 hex digits naming a Unicode scalar value.
+    ///
+    /// # Returns
+    /// * The character the escape stands for
+    ///
+    /// # Errors
+    /// * When the escape is unknown or names no character
+    fn escape(&mut self) -> Result<char, LexError> {
+        let start = self.mark();
+        self.advance(); // Skip the backslash
+        let simple = match self.current_char {
+            Some('n') => Some('\n'),
+            Some('t') => Some('\t'),
+            Some('r') => Some('\r'),
+            Some('0') => Some('\0'),
+            Some(c @ ('\\' | '\'' | '"')) => Some(c),
+            _ => None,
+        };
+        if let Some(c) = simple {
             self.advance();
+            return Ok(c);
         }
 
-        panic!("Unterminated string literal");
+        let mut value = None;
+        if self.current_char == Some('u') {
+            self.advance();
+            if self.current_char == Some('{') {
+                self.advance();
+                let digits_start = self.position;
+                while self.current_char.is_some_and(|c| c.is_ascii_hexdigit()) {
+                    self.advance();
+                }
+                let digits = &self.source[digits_start..self.position];
+                if self.current_char == Some('}') {
+                    self.advance();
+                    value = u32::from_str_radix(digits, 16)
+                        .ok()
+                        .filter(|_| digits.len() <= 6)
+                        .and_then(char::from_u32);
+                }
+            }
+        } else if self.current_char.is_some_and(|c| c != '\n') {
+            self.advance(); // Include the unknown character in the error
+        }
+        value.ok_or_else(|| {
+            let span = self.span_from(start);
+            LexError::new(
+                LexErrorKind::InvalidEscape(self.source[span.start..span.end].to_string()),
+                span,
+            )
+        })
     }
 }
 
+/// Returns true if a line ending in `token` must continue on the next line,
+/// as with a trailing binary operator or comma
+fn continues_line(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Plus
+            | Token::Minus
+            | Token::Asterisk
+            | Token::Slash
+            | Token::EqualEqual
+            | Token::NotEqual
+            | Token::Less
+            | Token::LessEqual
+            | Token::Greater
+            | Token::GreaterEqual
+            | Token::And
+            | Token::Or
+            | Token::Not
+            | Token::Assign
+            | Token::Comma
+            | Token::Arrow
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -260,75 +885,400 @@ mod tests {
     #[test]
     fn test_lexer_numbers() {
         let mut lexer = Lexer::new("123 456");
-        assert_eq!(lexer.get_next_token(), Token::Number(123));
-        assert_eq!(lexer.get_next_token(), Token::Number(456));
-        assert_eq!(lexer.get_next_token(), Token::EOF);
+        assert_eq!(lexer.get_next_token(), Ok(Token::Number(123)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Number(456)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::EOF));
+    }
+
+    #[test]
+    fn test_lexer_floats() {
+        let mut lexer = Lexer::new("3.25 .5 1e-3 2.5E10 7e+2 42");
+        assert_eq!(lexer.get_next_token(), Ok(Token::Float(3.25)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Float(0.5)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Float(0.001)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Float(2.5e10)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Float(700.0)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Number(42)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::EOF));
+    }
+
+    #[test]
+    fn test_lexer_float_overflow() {
+        let mut lexer = Lexer::new("1e999");
+        let error = lexer.get_next_token().unwrap_err();
+        assert_eq!(error.kind, LexErrorKind::NumericOverflow("1e999".to_string()));
     }
 
     #[test]
     fn test_lexer_operators() {
         let mut lexer = Lexer::new("+ - * /");
-        assert_eq!(lexer.get_next_token(), Token::Plus);
-        assert_eq!(lexer.get_next_token(), Token::Minus);
-        assert_eq!(lexer.get_next_token(), Token::Asterisk);
-        assert_eq!(lexer.get_next_token(), Token::Slash);
-        assert_eq!(lexer.get_next_token(), Token::EOF);
+        assert_eq!(lexer.get_next_token(), Ok(Token::Plus));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Minus));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Asterisk));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Slash));
+        assert_eq!(lexer.get_next_token(), Ok(Token::EOF));
     }
 
     #[test]
     fn test_lexer_identifiers_and_types() {
         let mut lexer = Lexer::new("x int str64 float32");
-        assert_eq!(lexer.get_next_token(), Token::Identifier("x".to_string()));
-        assert_eq!(lexer.get_next_token(), Token::TypeInt);
-        assert_eq!(lexer.get_next_token(), Token::TypeStr64);
-        assert_eq!(lexer.get_next_token(), Token::TypeFloat32);
+        assert_eq!(lexer.get_next_token(), Ok(Token::Identifier("x".to_string())));
+        assert_eq!(lexer.get_next_token(), Ok(Token::TypeInt));
+        assert_eq!(lexer.get_next_token(), Ok(Token::TypeStr64));
+        assert_eq!(lexer.get_next_token(), Ok(Token::TypeFloat32));
     }
 
     #[test]
     fn test_lexer_complex_expression() {
         let mut lexer = Lexer::new("x: int = 42;");
-        assert_eq!(lexer.get_next_token(), Token::Identifier("x".to_string()));
-        assert_eq!(lexer.get_next_token(), Token::Colon);
-        assert_eq!(lexer.get_next_token(), Token::TypeInt);
-        assert_eq!(lexer.get_next_token(), Token::Assign);
-        assert_eq!(lexer.get_next_token(), Token::Number(42));
-        assert_eq!(lexer.get_next_token(), Token::Semicolon);
-        assert_eq!(lexer.get_next_token(), Token::EOF);
+        assert_eq!(lexer.get_next_token(), Ok(Token::Identifier("x".to_string())));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Colon));
+        assert_eq!(lexer.get_next_token(), Ok(Token::TypeInt));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Assign));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Number(42)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Semicolon));
+        assert_eq!(lexer.get_next_token(), Ok(Token::EOF));
+    }
+
+    #[test]
+    fn test_lexer_comparison_and_logic() {
+        let mut lexer = Lexer::new("== != < <= > >= = and or not true false bool");
+        assert_eq!(lexer.get_next_token(), Ok(Token::EqualEqual));
+        assert_eq!(lexer.get_next_token(), Ok(Token::NotEqual));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Less));
+        assert_eq!(lexer.get_next_token(), Ok(Token::LessEqual));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Greater));
+        assert_eq!(lexer.get_next_token(), Ok(Token::GreaterEqual));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Assign));
+        assert_eq!(lexer.get_next_token(), Ok(Token::And));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Or));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Not));
+        assert_eq!(lexer.get_next_token(), Ok(Token::True));
+        assert_eq!(lexer.get_next_token(), Ok(Token::False));
+        assert_eq!(lexer.get_next_token(), Ok(Token::TypeBool));
+        assert_eq!(lexer.get_next_token(), Ok(Token::EOF));
+    }
+
+    #[test]
+    fn test_lexer_lone_bang() {
+        let mut lexer = Lexer::new("!x");
+        let error = lexer.get_next_token().unwrap_err();
+        assert_eq!(error.kind, LexErrorKind::UnexpectedChar('!'));
+    }
+
+    #[test]
+    fn test_lexer_conditionals() {
+        let mut lexer = Lexer::new("if x { } elif y { } else { }");
+        assert_eq!(lexer.get_next_token(), Ok(Token::If));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Identifier("x".to_string())));
+        assert_eq!(lexer.get_next_token(), Ok(Token::LBrace));
+        assert_eq!(lexer.get_next_token(), Ok(Token::RBrace));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Elif));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Identifier("y".to_string())));
+        assert_eq!(lexer.get_next_token(), Ok(Token::LBrace));
+        assert_eq!(lexer.get_next_token(), Ok(Token::RBrace));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Else));
+        assert_eq!(lexer.get_next_token(), Ok(Token::LBrace));
+        assert_eq!(lexer.get_next_token(), Ok(Token::RBrace));
+        assert_eq!(lexer.get_next_token(), Ok(Token::EOF));
+    }
+
+    #[test]
+    fn test_lexer_loops() {
+        let mut lexer = Lexer::new("while x { break; continue; }");
+        assert_eq!(lexer.get_next_token(), Ok(Token::While));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Identifier("x".to_string())));
+        assert_eq!(lexer.get_next_token(), Ok(Token::LBrace));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Break));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Semicolon));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Continue));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Semicolon));
+        assert_eq!(lexer.get_next_token(), Ok(Token::RBrace));
+
+        let mut lexer = Lexer::new("for i in range(0, 3) {}");
+        assert_eq!(lexer.get_next_token(), Ok(Token::For));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Identifier("i".to_string())));
+        assert_eq!(lexer.get_next_token(), Ok(Token::In));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Identifier("range".to_string())));
+    }
+
+    #[test]
+    fn test_lexer_functions() {
+        let mut lexer = Lexer::new("def add(a: int, b) -> int { return a - b; }");
+        assert_eq!(lexer.get_next_token(), Ok(Token::Def));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Identifier("add".to_string())));
+        assert_eq!(lexer.get_next_token(), Ok(Token::LParen));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Identifier("a".to_string())));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Colon));
+        assert_eq!(lexer.get_next_token(), Ok(Token::TypeInt));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Comma));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Identifier("b".to_string())));
+        assert_eq!(lexer.get_next_token(), Ok(Token::RParen));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Arrow));
+        assert_eq!(lexer.get_next_token(), Ok(Token::TypeInt));
+        assert_eq!(lexer.get_next_token(), Ok(Token::LBrace));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Return));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Identifier("a".to_string())));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Minus));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Identifier("b".to_string())));
+    }
+
+    /// Collects every token up to and including EOF
+    fn tokens(mut lexer: Lexer) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        loop {
+            let token = lexer.get_next_token().unwrap();
+            tokens.push(token.clone());
+            if token == Token::EOF {
+                return tokens;
+            }
+        }
+    }
+
+    #[test]
+    fn test_lexer_indentation() {
+        let input = "if x:\n    y = (1 +\n  2)\n\n    // comment\n    if y:\n        print y\nprint x";
+        let ident = |name: &str| Token::Identifier(name.to_string());
+        assert_eq!(
+            tokens(Lexer::with_indentation(input)),
+            vec![
+                Token::If, ident("x"), Token::Colon, Token::Newline,
+                Token::Indent, ident("y"), Token::Assign, Token::LParen, Token::Number(1),
+                Token::Plus, Token::Number(2), Token::RParen, Token::Newline,
+                Token::If, ident("y"), Token::Colon, Token::Newline,
+                Token::Indent, Token::Print, ident("y"), Token::Newline,
+                Token::Dedent, Token::Dedent, Token::Print, ident("x"), Token::Newline,
+                Token::EOF,
+            ]
+        );
+
+        // Indentation is ignored inside braces, and brace mode has no layout tokens
+        let input = "// ruspy: indent\nif x {\n  y;\n}\n";
+        assert_eq!(
+            tokens(Lexer::new(input)),
+            vec![
+                Token::If, ident("x"), Token::LBrace, Token::Newline,
+                ident("y"), Token::Semicolon, Token::Newline, Token::RBrace, Token::Newline,
+                Token::EOF,
+            ]
+        );
+        assert!(!tokens(Lexer::new("if x:\n    y\n")).contains(&Token::Indent));
+        assert!(tokens(Lexer::for_file("if x:\n    y\n", "main.rpy")).contains(&Token::Indent));
+    }
+
+    #[test]
+    fn test_lexer_indentation_errors() {
+        let mut lexer = Lexer::with_indentation("if x:\n \ty\n");
+        let errors: Vec<_> = std::iter::from_fn(|| match lexer.next_token() {
+            Ok(token) if token.node == Token::EOF => None,
+            result => Some(result),
+        })
+        .filter_map(Result::err)
+        .collect();
+        assert_eq!(errors, vec![LexError::new(LexErrorKind::MixedIndentation, Span::new(6, 8, 2, 1))]);
+
+        let mut lexer = Lexer::with_indentation("if x:\n    y\n  z\n");
+        let kinds: Vec<_> = std::iter::from_fn(|| match lexer.next_token() {
+            Ok(token) if token.node == Token::EOF => None,
+            result => Some(result),
+        })
+        .filter_map(|result| result.err().map(|e| e.kind))
+        .collect();
+        assert_eq!(kinds, vec![LexErrorKind::InconsistentDedent]);
     }
 
     #[test]
     fn test_lexer_whitespace_handling() {
         let mut lexer = Lexer::new("   42   +   58   ");
-        assert_eq!(lexer.get_next_token(), Token::Number(42));
-        assert_eq!(lexer.get_next_token(), Token::Plus);
-        assert_eq!(lexer.get_next_token(), Token::Number(58));
-        assert_eq!(lexer.get_next_token(), Token::EOF);
+        assert_eq!(lexer.get_next_token(), Ok(Token::Number(42)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Plus));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Number(58)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::EOF));
     }
 
     #[test]
     fn test_lexer_invalid_character() {
-        let mut lexer = Lexer::new("@");
-        let result = std::panic::catch_unwind(move || {
-            lexer.get_next_token();
-        });
-        assert!(result.is_err());
+        let mut lexer = Lexer::new("x @");
+        assert_eq!(lexer.get_next_token(), Ok(Token::Identifier("x".to_string())));
+        assert_eq!(
+            lexer.get_next_token(),
+            Err(LexError::new(
+                LexErrorKind::UnexpectedChar('@'),
+                Span::new(2, 3, 1, 3)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_lexer_unterminated_string() {
+        let mut lexer = Lexer::new("print \"hello");
+        assert_eq!(lexer.get_next_token(), Ok(Token::Print));
+        let error = lexer.get_next_token().unwrap_err();
+        assert_eq!(error.kind, LexErrorKind::UnterminatedString);
+        assert_eq!(error.span, Span::new(6, 12, 1, 7));
+    }
+
+    #[test]
+    fn test_lexer_char_literals() {
+        let input = r"'a' 'é' '\n' '\'' '\\' '\u{1F600}' '\u{e9}'";
+        let expected = ['a', 'é', '\n', '\'', '\\', '😀', 'é'];
+        let mut lexer = Lexer::new(input);
+        for c in expected {
+            assert_eq!(lexer.get_next_token(), Ok(Token::CharLiteral(c)));
+        }
+        assert_eq!(lexer.get_next_token(), Ok(Token::EOF));
+
+        let mut lexer = Lexer::new("'é' + 'b'");
+        assert_eq!(lexer.next_token().unwrap().span, Span::new(0, 4, 1, 1));
+        lexer.next_token().unwrap(); // +
+        assert_eq!(lexer.next_token().unwrap().span, Span::new(7, 10, 1, 7));
+    }
+
+    #[test]
+    fn test_lexer_char_errors() {
+        let cases = [
+            ("''", LexErrorKind::InvalidCharLiteral, Span::new(0, 2, 1, 1)),
+            ("'ab'", LexErrorKind::InvalidCharLiteral, Span::new(0, 4, 1, 1)),
+            ("'a\n'", LexErrorKind::UnterminatedChar, Span::new(0, 2, 1, 1)),
+            ("'\\q'", LexErrorKind::InvalidEscape("\\q".to_string()), Span::new(1, 3, 1, 2)),
+            ("'\\u{D800}'", LexErrorKind::InvalidEscape("\\u{D800}".to_string()), Span::new(1, 9, 1, 2)),
+            ("'\\u{1234567}'", LexErrorKind::InvalidEscape("\\u{1234567}".to_string()), Span::new(1, 12, 1, 2)),
+            ("'\\u41'", LexErrorKind::InvalidEscape("\\u".to_string()), Span::new(1, 3, 1, 2)),
+        ];
+        for (input, kind, span) in cases {
+            let mut lexer = Lexer::new(input);
+            assert_eq!(lexer.get_next_token(), Err(LexError::new(kind, span)), "{}", input);
+        }
+
+        // Lexing resumes after the closing quote
+        let mut lexer = Lexer::new("'\\q' x");
+        assert!(lexer.get_next_token().is_err());
+        assert_eq!(lexer.get_next_token(), Ok(Token::Identifier("x".to_string())));
+    }
+
+    #[test]
+    fn test_lexer_string_escapes() {
+        let input = r#""a\tb\n" "say \"hi\"" "back\\slash" "\u{1F600}\u{e9}" "it's \'q\'" "\0""#;
+        let expected = ["a\tb\n", "say \"hi\"", "back\\slash", "😀é", "it's 'q'", "\0"];
+        let mut lexer = Lexer::new(input);
+        for text in expected {
+            assert_eq!(lexer.get_next_token(), Ok(Token::StringLiteral(text.to_string())));
+        }
+        assert_eq!(lexer.get_next_token(), Ok(Token::EOF));
+
+        let cases = [
+            (r#""a\qb""#, "\\q", Span::new(2, 4, 1, 3)),
+            (r#""\u{110000}""#, "\\u{110000}", Span::new(1, 11, 1, 2)),
+            (r#""\u{}""#, "\\u{}", Span::new(1, 5, 1, 2)),
+            ("\"é\\x\"", "\\x", Span::new(3, 5, 1, 3)),
+        ];
+        for (input, escape, span) in cases {
+            let mut lexer = Lexer::new(input);
+            let expected = LexError::new(LexErrorKind::InvalidEscape(escape.to_string()), span);
+            assert_eq!(lexer.get_next_token(), Err(expected), "{}", input);
+            assert_eq!(lexer.get_next_token(), Ok(Token::EOF), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_lexer_multi_line_and_raw_strings() {
+        let input = "x = \"\"\"first \"line\"\n\tsecond\\n\"\"\" + r\"C:\\new\" + r\"\"\"a\\tb\n\"\"\"\nr";
+        let mut lexer = Lexer::new(input);
+        lexer.next_token().unwrap(); // x
+        lexer.next_token().unwrap(); // =
+        let text = lexer.next_token().unwrap();
+        assert_eq!(text.node, Token::StringLiteral("first \"line\"\n\tsecond\n".to_string()));
+        assert_eq!(text.span, Span::new(4, 32, 1, 5));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Plus));
+        assert_eq!(lexer.get_next_token(), Ok(Token::StringLiteral("C:\\new".to_string())));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Plus));
+        let raw = lexer.next_token().unwrap();
+        assert_eq!(raw.node, Token::StringLiteral("a\\tb\n".to_string()));
+        assert_eq!(raw.span, Span::new(47, 59, 2, 28));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Newline));
+        assert_eq!(lexer.next_token().unwrap().span, Span::new(60, 61, 4, 1));
+
+        // Plain strings end at the end of their line, triple-quoted ones at the end of input
+        let mut lexer = Lexer::new("\"one\ntwo\"");
+        let error = lexer.get_next_token().unwrap_err();
+        assert_eq!(error, LexError::new(LexErrorKind::UnterminatedString, Span::new(0, 4, 1, 1)));
+        let mut lexer = Lexer::new("\"\"\"one\ntwo\"\"");
+        let error = lexer.get_next_token().unwrap_err();
+        assert_eq!(error, LexError::new(LexErrorKind::UnterminatedString, Span::new(0, 12, 1, 1)));
+    }
+
+    #[test]
+    fn test_lexer_numeric_overflow() {
+        let mut lexer = Lexer::new("99999999999999999999");
+        let error = lexer.get_next_token().unwrap_err();
+        assert_eq!(
+            error.kind,
+            LexErrorKind::NumericOverflow("99999999999999999999".to_string())
+        );
+        assert_eq!(error.span, Span::new(0, 20, 1, 1));
     }
 
     #[test]
     fn test_lexer_comments() {
         let mut lexer = Lexer::new("42 // This is a comment\n+ 58");
-        assert_eq!(lexer.get_next_token(), Token::Number(42));
-        assert_eq!(lexer.get_next_token(), Token::Plus);
-        assert_eq!(lexer.get_next_token(), Token::Number(58));
-        assert_eq!(lexer.get_next_token(), Token::EOF);
+        assert_eq!(lexer.get_next_token(), Ok(Token::Number(42)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Newline));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Plus));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Number(58)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::EOF));
+    }
+
+    #[test]
+    fn test_lexer_spans() {
+        let mut lexer = Lexer::new("x: int = 42;\n  print x;");
+        let x = lexer.next_token().unwrap();
+        assert_eq!(x.node, Token::Identifier("x".to_string()));
+        assert_eq!(x.span, Span::new(0, 1, 1, 1));
+        lexer.next_token().unwrap(); // :
+        let int = lexer.next_token().unwrap();
+        assert_eq!(int.span, Span::new(3, 6, 1, 4));
+        lexer.next_token().unwrap(); // =
+        let number = lexer.next_token().unwrap();
+        assert_eq!(number.span, Span::new(9, 11, 1, 10));
+        lexer.next_token().unwrap(); // ;
+        let newline = lexer.next_token().unwrap();
+        assert_eq!(newline.node, Token::Newline);
+        assert_eq!(newline.span, Span::new(12, 13, 1, 13));
+        let print = lexer.next_token().unwrap();
+        assert_eq!(print.node, Token::Print);
+        assert_eq!(print.span, Span::new(15, 20, 2, 3));
+    }
+
+    #[test]
+    fn test_lexer_line_continuation() {
+        let mut lexer = Lexer::new("x = 1 +\n  2\nf(a,\n b)\n\n");
+        let expected = [
+            Token::Identifier("x".to_string()),
+            Token::Assign,
+            Token::Number(1),
+            Token::Plus,
+            Token::Number(2),
+            Token::Newline,
+            Token::Identifier("f".to_string()),
+            Token::LParen,
+            Token::Identifier("a".to_string()),
+            Token::Comma,
+            Token::Identifier("b".to_string()),
+            Token::RParen,
+            Token::Newline,
+            Token::EOF,
+        ];
+        for token in expected {
+            assert_eq!(lexer.get_next_token(), Ok(token));
+        }
     }
 
     #[test]
     fn test_lexer_comments_at_end() {
         let mut lexer = Lexer::new("42 + 58 // Final comment");
-        assert_eq!(lexer.get_next_token(), Token::Number(42));
-        assert_eq!(lexer.get_next_token(), Token::Plus);
-        assert_eq!(lexer.get_next_token(), Token::Number(58));
-        assert_eq!(lexer.get_next_token(), Token::EOF);
+        assert_eq!(lexer.get_next_token(), Ok(Token::Number(42)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Plus));
+        assert_eq!(lexer.get_next_token(), Ok(Token::Number(58)));
+        assert_eq!(lexer.get_next_token(), Ok(Token::EOF));
     }
 }
@@ -1,26 +1,57 @@
+mod diagnostics;
 mod interpreter;
 mod lexer;
 mod parser;
+mod repl;
+mod semantic;
 mod types;
 
-use clap::{Parser as ClapParser, ArgAction};
-use env_logger;
+use clap::{Parser as ClapParser, ArgAction, Subcommand};
+use diagnostics::Diagnostic;
 use interpreter::Interpreter;
 use lexer::Lexer;
 use log::{debug, info, error};
-use parser::Parser;
+use parser::{Node, Parser};
+use repl::Repl;
+use semantic::Analysis;
 use std::fs;
+use std::io;
 use std::process;
+use std::thread;
+
+/// Native stack size for the thread running the interpreter, large enough for
+/// the deepest recursion the interpreter allows
+const STACK_SIZE: usize = 64 * 1024 * 1024;
 
 #[derive(ClapParser)]
-#[command(version, about = "A simple interpreter written in Rust")]
+#[command(
+    version,
+    about = "A simple interpreter written in Rust",
+    args_conflicts_with_subcommands = true
+)]
 struct Cli {
     /// Enable debug mode
-    #[arg(short = 'd', long = "debug", action = ArgAction::SetTrue)]
+    #[arg(short = 'd', long = "debug", action = ArgAction::SetTrue, global = true)]
     debug: bool,
 
-    /// Source file to interpret
-    file: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Source file to interpret; starts an interactive session if omitted
+    file: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Report every syntax and type error in a source file without running it
+    Check {
+        /// Source file to check
+        file: String,
+
+        /// Print the inferred type at a 1-based LINE:COLUMN, e.g. for an editor hover
+        #[arg(long = "type-at", value_name = "LINE:COLUMN", value_parser = parse_position)]
+        type_at: Option<(usize, usize)>,
+    },
 }
 
 fn main() -> Result<(), String> {
@@ -38,36 +69,145 @@ fn main() -> Result<(), String> {
             .init();
     }
 
-    info!("Starting Ruspy interpreter");
-    
-    // Read the source file
-    let source = match fs::read_to_string(&cli.file) {
+    let worker = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || match (cli.command, cli.file) {
+            (Some(Command::Check { file, type_at }), _) => check_file(&file, type_at),
+            (None, Some(file)) => run_file(&file, cli.debug),
+            (None, None) => run_repl(),
+        })
+        .map_err(|e| format!("Failed to start interpreter thread: {}", e))?;
+    worker
+        .join()
+        .unwrap_or_else(|_| Err("Interpreter thread panicked".to_string()))
+}
+
+/// Starts an interactive session on stdin/stdout
+fn run_repl() -> Result<(), String> {
+    println!("Ruspy {} interactive mode, type :help for commands", env!("CARGO_PKG_VERSION"));
+    let mut repl = Repl::new(io::stdout(), diagnostics::use_color(&io::stdout()));
+    repl.run(io::stdin().lock()).map_err(|e| e.to_string())
+}
+
+/// Reads a source file, exiting the process if it cannot be read
+fn read_source(file: &str) -> String {
+    match fs::read_to_string(file) {
         Ok(content) => content,
         Err(e) => {
-            error!("Error reading file '{}': {}", cli.file, e);
+            error!("Error reading file '{}': {}", file, e);
             process::exit(1);
         }
+    }
+}
+
+/// Parses `source`, reporting every syntax error found
+///
+/// # Returns
+/// * The AST, or None if any syntax errors were reported
+fn parse_source(source: &str, file: &str) -> Option<Vec<Node>> {
+    let lexer = Lexer::for_file(source, file);
+    let mut parser = Parser::new(lexer);
+    match parser.parse() {
+        Ok(ast) => Some(ast),
+        Err(errors) => {
+            for e in &errors {
+                diagnostics::emit(&Diagnostic::from(e), source, file);
+            }
+            diagnostics::emit_summary(errors.len());
+            None
+        }
+    }
+}
+
+/// Runs the semantic checks on a parsed program, reporting every error found
+///
+/// # Returns
+/// * The analysis, or None if any errors were reported
+fn check_program(ast: &[Node], source: &str, file: &str) -> Option<Analysis> {
+    let analysis = semantic::analyze(ast);
+    for (name, signature) in &analysis.signatures {
+        debug!("Inferred signature of {}: {}", name, signature);
+    }
+    if analysis.errors.is_empty() {
+        return Some(analysis);
+    }
+    for e in &analysis.errors {
+        diagnostics::emit(&Diagnostic::from(e), source, file);
+    }
+    diagnostics::emit_summary(analysis.errors.len());
+    None
+}
+
+/// Parses a `LINE:COLUMN` position given on the command line
+fn parse_position(position: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("expected LINE:COLUMN, found '{}'", position);
+    let (line, column) = position.split_once(':').ok_or_else(invalid)?;
+    let line = line.parse().map_err(|_| invalid())?;
+    let column = column.parse().map_err(|_| invalid())?;
+    if line == 0 || column == 0 {
+        return Err("lines and columns start at 1".to_string());
+    }
+    Ok((line, column))
+}
+
+/// Converts a 1-based line and character column to a byte offset into `source`
+fn byte_offset(source: &str, line: usize, column: usize) -> Option<usize> {
+    let start: usize = source.split_inclusive('\n').take(line - 1).map(str::len).sum();
+    let text = source.get(start..)?.lines().next()?;
+    text.char_indices().nth(column - 1).map(|(i, _)| start + i)
+}
+
+/// Checks a source file for syntax and type errors without executing it
+///
+/// # Arguments
+/// * `file` - Path of the source file
+/// * `type_at` - Line and column to print the inferred type of, if any
+fn check_file(file: &str, type_at: Option<(usize, usize)>) -> Result<(), String> {
+    info!("Starting Ruspy interpreter");
+    let source = read_source(file);
+    info!("Checking file: {}", file);
+
+    let Some(ast) = parse_source(&source, file) else {
+        process::exit(1);
+    };
+    let Some(analysis) = check_program(&ast, &source, file) else {
+        process::exit(1);
     };
+    if let Some((line, column)) = type_at {
+        let offset = byte_offset(&source, line, column)
+            .ok_or_else(|| format!("{}:{}:{} is past the end of the file", file, line, column))?;
+        match analysis.type_at(offset) {
+            Some(ty) => println!("{}", ty),
+            None => println!("unknown"),
+        }
+        return Ok(());
+    }
+    info!("No errors found in {}", file);
+    Ok(())
+}
 
-    info!("Processing file: {}", cli.file);
+/// Parses and executes a source file
+fn run_file(file: &str, debug: bool) -> Result<(), String> {
+    info!("Starting Ruspy interpreter");
 
-    // Create lexer and generate tokens
-    let lexer = Lexer::new(&source);
-    let mut parser = Parser::new(lexer);
+    // Read the source file
+    let source = read_source(file);
+
+    info!("Processing file: {}", file);
 
     // Parse the source code
-    let ast = match parser.parse() {
-        Ok(ast) => {
-            if cli.debug {
+    let ast = match parse_source(&source, file) {
+        Some(ast) => {
+            if debug {
                 debug!("Generated AST: {:?}", ast);
             }
             ast
         },
-        Err(e) => {
-            error!("Parsing error: {}", e);
-            process::exit(1);
-        }
+        None => process::exit(1),
     };
+    if check_program(&ast, &source, file).is_none() {
+        process::exit(1);
+    }
 
     // Create interpreter and execute the code
     let mut interpreter = Interpreter::new();
@@ -78,7 +218,7 @@ fn main() -> Result<(), String> {
             Ok(())
         },
         Err(e) => {
-            error!("Runtime error: {}", e);
+            diagnostics::emit(&Diagnostic::from(&e), &source, file);
             process::exit(1);
         }
     }
@@ -3,30 +3,156 @@
 /// This module is responsible for parsing tokens from the lexer into an Abstract Syntax Tree (AST).
 /// It implements a recursive descent parser that handles variable declarations, assignments,
 /// and arithmetic expressions.
-use crate::lexer::{Lexer, Token};
-use crate::types::RuspyType;
-use log::{debug, error};
+use crate::diagnostics::SourceError;
+use crate::lexer::{LexError, LexErrorKind, Lexer, Span, Spanned, Token, INDENT_EXTENSION, INDENT_PRAGMA};
+use crate::types::{OverflowMode, Type};
+use log::debug;
+use std::rc::Rc;
+
+/// An AST node together with the span of source it was parsed from
+pub type Node = Spanned<ASTNode>;
+
+/// Error produced when the token stream does not form a valid program
+pub type ParseError = SourceError;
+
+impl From<LexError> for ParseError {
+    fn from(error: LexError) -> Self {
+        let parse_error = ParseError::new(error.kind.code(), error.kind.to_string(), error.span);
+        match error.kind {
+            LexErrorKind::UnexpectedChar(_) => parse_error,
+            LexErrorKind::UnterminatedString => {
+                parse_error.with_help(
+                    "add a closing `\"` to end the string, or use `\"\"\"` quotes \
+                     for one spanning several lines",
+                )
+            }
+            LexErrorKind::UnterminatedChar => {
+                parse_error.with_help("add a closing `'` to end the char")
+            }
+            LexErrorKind::InvalidCharLiteral => {
+                parse_error.with_help("use double quotes for a string, e.g. \"ab\"")
+            }
+            LexErrorKind::InvalidEscape(_) => parse_error.with_help(
+                "valid escapes are \\n, \\t, \\r, \\0, \\\\, \\', \\\" and \\u{...} \
+                 with 1 to 6 hex digits naming a Unicode character",
+            ),
+            LexErrorKind::NumericOverflow(_) => {
+                parse_error.with_help("integer literals must fit in a 64-bit signed integer")
+            }
+            LexErrorKind::MixedIndentation => {
+                parse_error.with_help("indent with either tabs or spaces throughout the file")
+            }
+            LexErrorKind::InconsistentDedent => {
+                parse_error.with_help("line this up with one of the enclosing blocks")
+            }
+        }
+    }
+}
 
 /// Represents nodes in the Abstract Syntax Tree (AST)
 ///
 /// Each variant represents a different kind of program construct that can appear
-/// in the source code.
+/// in the source code. Child nodes are wrapped in [`Node`] so they carry their spans.
 #[derive(Debug, PartialEq)]
 pub enum ASTNode {
     /// Represents a numeric literal value
     Number(i64),
+    /// Represents a floating-point literal value
+    Float(f64),
     /// Represents a string literal value
     StringLiteral(String),
+    /// Represents a char literal value
+    CharLiteral(char),
     /// Represents a variable reference
     Identifier(String),
-    /// Represents a binary operation (e.g., addition, multiplication)
-    BinaryOp(Box<ASTNode>, Token, Box<ASTNode>),
+    /// Represents a function call: function name and arguments
+    Call(String, Vec<Node>),
+    /// Represents a boolean literal value
+    Bool(bool),
+    /// Represents a binary operation (e.g., addition, multiplication, comparison)
+    BinaryOp(Box<Node>, Token, Box<Node>),
+    /// Represents a short-circuiting `and` / `or` operation
+    LogicalOp(Box<Node>, Token, Box<Node>),
+    /// Represents a unary operation such as `not`
+    UnaryOp(Token, Box<Node>),
+    /// Represents an explicit conversion such as `int32(x)`: target type and value
+    Cast(Type, Box<Node>),
     /// Represents an untyped variable assignment
-    VarAssign(String, Box<ASTNode>),
+    VarAssign(String, Box<Node>),
     /// Represents a typed variable assignment with type annotation
-    TypedVarAssign(String, RuspyType, Box<ASTNode>),
+    TypedVarAssign(String, Type, Box<Node>),
     /// Represents a print statement
-    Print(Box<ASTNode>),
+    Print(Box<Node>),
+    /// Represents a sequence of statements enclosed in braces
+    Block(Vec<Node>),
+    /// Represents a conditional: condition, then-block and optional else branch
+    ///
+    /// `elif` chains are stored as a nested `If` in the else branch.
+    If(Box<Node>, Box<Node>, Option<Box<Node>>),
+    /// Represents a loop: condition and body
+    While(Box<Node>, Box<Node>),
+    /// Represents `for name in iterable { ... }`: loop variable, iterable and body
+    For(String, Box<Node>, Box<Node>),
+    /// Represents `break`, leaving the innermost loop
+    Break,
+    /// Represents `continue`, skipping to the next iteration of the innermost loop
+    Continue,
+    /// Represents a function definition
+    FunctionDef(Rc<Function>),
+    /// Represents `return`, with an optional value
+    Return(Option<Box<Node>>),
+    /// Represents a `wrapping { ... }` or `saturating { ... }` block, whose
+    /// integer arithmetic uses the given overflow mode
+    OverflowBlock(OverflowMode, Box<Node>),
+    /// Placeholder for a statement that failed to parse
+    ///
+    /// Produced during error recovery so the rest of the program can still be
+    /// parsed; a tree containing it is never executed.
+    Error,
+}
+
+/// A user-defined function
+///
+/// Shared through an `Rc` so the interpreter can keep it after the AST that
+/// defined it has been dropped, e.g. between REPL inputs.
+#[derive(Debug, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<Parameter>,
+    /// Declared return type, if any
+    pub return_type: Option<Type>,
+    pub body: Node,
+    /// Overflow mode of the code the function was defined in
+    pub overflow_mode: OverflowMode,
+}
+
+/// A function parameter with its optional type annotation
+#[derive(Debug, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    pub param_type: Option<Type>,
+    pub span: Span,
+}
+
+impl ASTNode {
+    /// Returns true for nodes that only compute a value, as opposed to
+    /// statements such as assignments and prints
+    pub fn is_expression(&self) -> bool {
+        matches!(
+            self,
+            ASTNode::Number(_)
+                | ASTNode::Float(_)
+                | ASTNode::StringLiteral(_)
+                | ASTNode::CharLiteral(_)
+                | ASTNode::Bool(_)
+                | ASTNode::Identifier(_)
+                | ASTNode::Call(..)
+                | ASTNode::BinaryOp(..)
+                | ASTNode::LogicalOp(..)
+                | ASTNode::UnaryOp(..)
+                | ASTNode::Cast(..)
+        )
+    }
 }
 
 /// Parser struct that maintains the state during parsing
@@ -35,6 +161,18 @@ pub struct Parser<'a> {
     lexer: Lexer<'a>,
     /// The current token being processed
     current_token: Token,
+    /// Span of the current token
+    current_span: Span,
+    /// Span of the most recently consumed token
+    previous_span: Span,
+    /// Errors collected while recovering from syntax errors
+    errors: Vec<ParseError>,
+    /// Number of loops enclosing the current position
+    loop_depth: usize,
+    /// Number of function definitions enclosing the current position
+    function_depth: usize,
+    /// Overflow mode of the innermost enclosing `wrapping` / `saturating` block
+    overflow_mode: OverflowMode,
 }
 
 impl<'a> Parser<'a> {
@@ -44,15 +182,52 @@ impl<'a> Parser<'a> {
     /// * `lexer` - The lexer that will provide tokens to parse
     ///
     /// # Returns
-    /// * A new Parser instance initialized with the first token
+    /// * A new Parser instance; the first token is read when parsing starts
     pub fn new(lexer: Lexer<'a>) -> Self {
-        let mut parser = Parser {
+        Parser {
             lexer,
             current_token: Token::EOF,
-        };
-        parser.current_token = parser.lexer.get_next_token();
-        debug!("Initial token: {:?}", parser.current_token);
-        parser
+            current_span: Span::default(),
+            previous_span: Span::default(),
+            errors: Vec::new(),
+            loop_depth: 0,
+            function_depth: 0,
+            overflow_mode: OverflowMode::Checked,
+        }
+    }
+
+    /// Moves on to the next token from the lexer
+    ///
+    /// When the lexer rejects some text, the current token becomes
+    /// `Token::Invalid` at that text, so recovery skips past it instead of
+    /// seeing the previous token again.
+    ///
+    /// # Errors
+    /// * When the lexer cannot produce the next token
+    fn advance(&mut self) -> Result<(), ParseError> {
+        self.previous_span = self.current_span;
+        match self.lexer.next_token() {
+            Ok(next) => {
+                self.current_token = next.node;
+                self.current_span = next.span;
+                Ok(())
+            }
+            Err(error) => {
+                self.current_token = Token::Invalid;
+                self.current_span = error.span;
+                Err(error.into())
+            }
+        }
+    }
+
+    /// Returns a span from `start` to the end of the last consumed token
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.previous_span)
+    }
+
+    /// Creates an error located at the current token
+    fn error_here(&self, code: &'static str, message: impl Into<String>) -> ParseError {
+        ParseError::new(code, message, self.current_span)
     }
 
     /// Consumes the current token if it matches the expected token
@@ -61,22 +236,30 @@ impl<'a> Parser<'a> {
     /// * `expected_token` - The token type that is expected at this point
     ///
     /// # Returns
-    /// * Result<(), String> - Ok if token is consumed, Err with message if not
-    fn eat(&mut self, expected_token: Token) -> Result<(), String> {
+    /// * Result<(), ParseError> - Ok if token is consumed, Err located at the current token if not
+    fn eat(&mut self, expected_token: Token) -> Result<(), ParseError> {
         debug!("Attempting to eat token: {:?}", expected_token);
         debug!("Current token: {:?}", self.current_token);
 
         if std::mem::discriminant(&self.current_token) == std::mem::discriminant(&expected_token) {
-            self.current_token = self.lexer.get_next_token();
+            self.advance()?;
             debug!("Token eaten successfully, next token: {:?}", self.current_token);
             Ok(())
         } else {
-            let error_msg = format!(
-                "Parser error: Expected token {:?}, found {:?}",
-                expected_token, self.current_token
+            let mut error = self.error_here(
+                "E0101",
+                format!(
+                    "Expected token {:?}, found {:?}",
+                    expected_token, self.current_token
+                ),
             );
-            error!("{}", error_msg);
-            Err(error_msg)
+            if expected_token == Token::Semicolon {
+                error = error
+                    .with_label(self.previous_span, "expected `;` or a line break after this")
+                    .with_help("put each statement on its own line, or separate them with `;`");
+            }
+            debug!("{}", error);
+            Err(error)
         }
     }
 
@@ -87,21 +270,162 @@ impl<'a> Parser<'a> {
     /// and expressions.
     ///
     /// # Returns
-    /// * `Result<Vec<ASTNode>, String>` - Either a vector of AST nodes or an error message
-    pub fn parse(&mut self) -> Result<Vec<ASTNode>, String> {
+    /// * `Result<Vec<Node>, Vec<ParseError>>` - Either a vector of AST nodes or
+    ///   every syntax error found in the input
+    pub fn parse(&mut self) -> Result<Vec<Node>, Vec<ParseError>> {
+        let (statements, errors) = self.parse_with_recovery();
+        if errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Parses the whole input, recovering from syntax errors
+    ///
+    /// When a statement fails to parse, the error is recorded, tokens are
+    /// skipped up to the next `;` or statement keyword, and an
+    /// [`ASTNode::Error`] node stands in for the broken statement.
+    ///
+    /// # Returns
+    /// * The partial AST and all errors encountered, in source order
+    pub fn parse_with_recovery(&mut self) -> (Vec<Node>, Vec<ParseError>) {
+        self.errors.clear();
+        self.advance_recovering();
+        debug!("Initial token: {:?}", self.current_token);
+
+        let statements = self.statement_list(|_| false);
+        (statements, std::mem::take(&mut self.errors))
+    }
+
+    /// Parses statements until `is_end` accepts the current token or EOF is
+    /// reached, leaving that token unconsumed
+    ///
+    /// Line breaks between statements are skipped, and a stray `}` is
+    /// reported and skipped since it can't start a statement.
+    fn statement_list(&mut self, is_end: impl Fn(&Token) -> bool) -> Vec<Node> {
         let mut statements = Vec::new();
-        
-        while self.current_token != Token::EOF {
-            let statement = self.statement()?;
-            statements.push(statement);
+        while self.current_token != Token::EOF && !is_end(&self.current_token) {
+            match self.current_token {
+                Token::Newline => self.advance_recovering(),
+                Token::RBrace => {
+                    let error = self.error_here("E0105", "Unmatched closing brace");
+                    self.errors.push(error);
+                    self.advance_recovering();
+                }
+                // Only left over when a brace block is missing its `}`
+                Token::Dedent => self.advance_recovering(),
+                _ => statements.push(self.statement_recovering()),
+            }
         }
-        
-        Ok(statements)
+        statements
     }
 
-    fn statement(&mut self) -> Result<ASTNode, String> {
+    /// Parses a statement, or records the error and returns an error node
+    fn statement_recovering(&mut self) -> Node {
+        match self.statement() {
+            Ok(statement) => statement,
+            Err(error) => {
+                let start = error.span;
+                self.errors.push(error);
+                self.synchronize();
+                Node::new(ASTNode::Error, self.span_from(start))
+            }
+        }
+    }
+
+    /// Advances to the next token, recording lexer errors until a valid token is found
+    fn advance_recovering(&mut self) {
+        while let Err(error) = self.advance() {
+            self.errors.push(error);
+        }
+    }
+
+    /// Skips tokens until a likely statement boundary
+    ///
+    /// Stops after a `;` or line break, or before a statement keyword, the
+    /// end of a block or EOF, so parsing can resume with the next statement.
+    /// A `{ ... }` block opened by the broken statement, such as the body
+    /// after a bad `if` condition, is skipped up to its matching `}`, along
+    /// with any `elif` / `else` branches that follow it.
+    fn synchronize(&mut self) {
+        let mut depth = 0;
+        loop {
+            match self.current_token {
+                Token::EOF => return,
+                Token::LBrace => {
+                    depth += 1;
+                    self.advance_recovering();
+                }
+                Token::RBrace if depth > 0 => {
+                    depth -= 1;
+                    self.advance_recovering();
+                    if depth == 0 && !matches!(self.current_token, Token::Elif | Token::Else) {
+                        return;
+                    }
+                }
+                _ if depth > 0 => self.advance_recovering(),
+                Token::RBrace
+                | Token::Dedent
+                | Token::Print
+                | Token::If
+                | Token::While
+                | Token::For
+                | Token::Def
+                | Token::Return
+                | Token::Wrapping
+                | Token::Saturating => return,
+                Token::Semicolon | Token::Newline => {
+                    self.advance_recovering();
+                    return;
+                }
+                _ => self.advance_recovering(),
+            }
+        }
+    }
+
+    /// Consumes the end of a simple statement: a `;` or a line break
+    ///
+    /// A closing `}` or the end of input also ends a statement, but is left
+    /// for the enclosing block or parse() to consume.
+    fn end_statement(&mut self) -> Result<(), ParseError> {
+        match self.current_token {
+            Token::Newline => self.advance(),
+            Token::RBrace | Token::EOF => Ok(()),
+            _ => self.eat(Token::Semicolon),
+        }
+    }
+
+    /// Returns true if the current token ends a simple statement
+    fn at_statement_end(&self) -> bool {
+        matches!(
+            self.current_token,
+            Token::Semicolon | Token::Newline | Token::RBrace | Token::EOF
+        )
+    }
+
+    /// Skips a line break if the token after it is `next`, so constructs
+    /// like `}` and `else` may sit on separate lines
+    fn skip_newline_before(&mut self, next: &[Token]) -> Result<(), ParseError> {
+        if self.current_token == Token::Newline
+            && self.peek_next().is_some_and(|token| next.contains(&token))
+        {
+            self.advance()?;
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<Node, ParseError> {
         match &self.current_token {
             Token::Print => self.print_statement(),
+            Token::If => self.if_statement(),
+            Token::While => self.while_statement(),
+            Token::For => self.for_statement(),
+            Token::Break | Token::Continue => self.loop_control_statement(),
+            Token::Def => self.function_definition(),
+            Token::Return => self.return_statement(),
+            Token::Wrapping | Token::Saturating => self.overflow_block(),
+            Token::Indent => self.unexpected_indentation(),
             Token::Identifier(_) => {
                 if self.peek_next() == Some(Token::Colon) {
                     self.variable_declaration_with_type()
@@ -115,13 +439,20 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn variable_declaration_with_type(&mut self) -> Result<ASTNode, String> {
-        // Get variable name
+    /// Consumes an identifier and returns its name
+    fn identifier(&mut self) -> Result<String, ParseError> {
         let name = match &self.current_token {
             Token::Identifier(name) => name.clone(),
-            _ => return Err("Expected identifier".to_string()),
+            _ => return Err(self.error_here("E0102", "Expected identifier")),
         };
         self.eat(Token::Identifier(name.clone()))?;
+        Ok(name)
+    }
+
+    fn variable_declaration_with_type(&mut self) -> Result<Node, ParseError> {
+        let start = self.current_span;
+        // Get variable name
+        let name = self.identifier()?;
         
         // Expect colon
         self.eat(Token::Colon)?;
@@ -133,110 +464,491 @@ impl<'a> Parser<'a> {
         self.eat(Token::Assign)?;
         
         // Get expression value
-        let value = self.expr()?;
+        let value = self.expression()?;
         
-        // Expect semicolon
-        self.eat(Token::Semicolon)?;
+        // Expect end of statement
+        self.end_statement()?;
         
-        Ok(ASTNode::TypedVarAssign(name, var_type, Box::new(value)))
+        Ok(Node::new(
+            ASTNode::TypedVarAssign(name, var_type, Box::new(value)),
+            self.span_from(start),
+        ))
     }
 
-    fn variable_declaration_without_type(&mut self) -> Result<ASTNode, String> {
+    fn variable_declaration_without_type(&mut self) -> Result<Node, ParseError> {
+        let start = self.current_span;
         // Get variable name
-        let name = match &self.current_token {
-            Token::Identifier(name) => name.clone(),
-            _ => return Err("Expected identifier".to_string()),
-        };
-        self.eat(Token::Identifier(name.clone()))?;
+        let name = self.identifier()?;
         
         // Expect assignment
         self.eat(Token::Assign)?;
         
         // Get expression value
-        let value = self.expr()?;
+        let value = self.expression()?;
         
-        // Expect semicolon
-        self.eat(Token::Semicolon)?;
+        // Expect end of statement
+        self.end_statement()?;
         
-        Ok(ASTNode::VarAssign(name, Box::new(value)))
+        Ok(Node::new(
+            ASTNode::VarAssign(name, Box::new(value)),
+            self.span_from(start),
+        ))
     }
 
-    fn parse_type(&mut self) -> Result<RuspyType, String> {
+    fn parse_type(&mut self) -> Result<Type, ParseError> {
         match &self.current_token {
             Token::TypeInt => {
                 self.eat(Token::TypeInt)?;
-                Ok(RuspyType::Int(0))
+                Ok(Type::Int)
             },
             Token::TypeInt32 => {
                 self.eat(Token::TypeInt32)?;
-                Ok(RuspyType::Int32(0))
+                Ok(Type::Int32)
             },
             Token::TypeInt64 => {
                 self.eat(Token::TypeInt64)?;
-                Ok(RuspyType::Int64(0))
+                Ok(Type::Int64)
             },
             Token::TypeFloat => {
                 self.eat(Token::TypeFloat)?;
-                Ok(RuspyType::Float(0.0))
+                Ok(Type::Float)
             },
             Token::TypeFloat32 => {
                 self.eat(Token::TypeFloat32)?;
-                Ok(RuspyType::Float32(0.0))
+                Ok(Type::Float32)
             },
             Token::TypeFloat64 => {
                 self.eat(Token::TypeFloat64)?;
-                Ok(RuspyType::Float64(0.0))
+                Ok(Type::Float64)
             },
             Token::TypeStr => {
                 self.eat(Token::TypeStr)?;
-                Ok(RuspyType::Str(String::new()))
+                Ok(Type::Str)
+            },
+            Token::TypeStr8 => {
+                self.eat(Token::TypeStr8)?;
+                Ok(Type::Str8)
+            },
+            Token::TypeStr32 => {
+                self.eat(Token::TypeStr32)?;
+                Ok(Type::Str32)
+            },
+            Token::TypeStr64 => {
+                self.eat(Token::TypeStr64)?;
+                Ok(Type::Str64)
             },
             Token::TypeChar => {
                 self.eat(Token::TypeChar)?;
-                Ok(RuspyType::Char('\0'))
+                Ok(Type::Char)
+            },
+            Token::TypeBool => {
+                self.eat(Token::TypeBool)?;
+                Ok(Type::Bool)
             },
-            _ => Err(format!("Invalid type: {:?}", self.current_token)),
+            _ => Err(self
+                .error_here("E0103", format!("Invalid type: {:?}", self.current_token))
+                .with_help("valid types are int, int32, int64, float, float32, float64, char, str, str8, str32, str64 \
+                            and bool")),
         }
     }
 
     // Helper method to peek at the next token without consuming it
     fn peek_next(&self) -> Option<Token> {
         let mut lexer_clone = self.lexer.clone();
-        let current_token_clone = self.current_token.clone();
-        
-        // Advance and get the next token
-        let next_token = lexer_clone.get_next_token();
-        
-        Some(next_token)
+
+        // Advance and get the next token; a lexer error is reported once the
+        // token is actually consumed, so it is ignored here
+        lexer_clone.get_next_token().ok()
+    }
+
+    /// Parses `if cond { ... }` with any number of `elif` branches and an
+    /// optional `else` branch
+    fn if_statement(&mut self) -> Result<Node, ParseError> {
+        let start = self.current_span;
+        // `elif` continues a chain exactly like a nested `if`
+        if self.current_token == Token::Elif {
+            self.eat(Token::Elif)?;
+        } else {
+            self.eat(Token::If)?;
+        }
+        let condition = self.expression()?;
+        let then_branch = self.block()?;
+
+        self.skip_newline_before(&[Token::Elif, Token::Else])?;
+        let else_branch = match self.current_token {
+            Token::Elif => Some(Box::new(self.if_statement()?)),
+            Token::Else => {
+                self.eat(Token::Else)?;
+                Some(Box::new(self.block()?))
+            }
+            _ => None,
+        };
+
+        Ok(Node::new(
+            ASTNode::If(Box::new(condition), Box::new(then_branch), else_branch),
+            self.span_from(start),
+        ))
+    }
+
+    /// Parses `while cond { ... }`
+    fn while_statement(&mut self) -> Result<Node, ParseError> {
+        let start = self.current_span;
+        self.eat(Token::While)?;
+        let condition = self.expression()?;
+
+        self.loop_depth += 1;
+        let body = self.block();
+        self.loop_depth -= 1;
+        let body = body?;
+
+        Ok(Node::new(
+            ASTNode::While(Box::new(condition), Box::new(body)),
+            self.span_from(start),
+        ))
+    }
+
+    /// Parses `for name in iterable { ... }`
+    fn for_statement(&mut self) -> Result<Node, ParseError> {
+        let start = self.current_span;
+        self.eat(Token::For)?;
+        let name = self.identifier()?;
+        self.eat(Token::In)?;
+        let iterable = self.expression()?;
+
+        self.loop_depth += 1;
+        let body = self.block();
+        self.loop_depth -= 1;
+        let body = body?;
+
+        Ok(Node::new(
+            ASTNode::For(name, Box::new(iterable), Box::new(body)),
+            self.span_from(start),
+        ))
+    }
+
+    /// Parses `break;` or `continue;`
+    ///
+    /// Using either outside a loop is recorded as an error, but the statement
+    /// is still consumed so parsing continues normally.
+    fn loop_control_statement(&mut self) -> Result<Node, ParseError> {
+        let start = self.current_span;
+        let token = self.current_token.clone();
+        self.eat(token.clone())?;
+        self.end_statement()?;
+        let span = self.span_from(start);
+
+        if self.loop_depth == 0 {
+            let keyword = if token == Token::Break { "break" } else { "continue" };
+            self.errors.push(
+                ParseError::new("E0107", format!("`{}` outside of a loop", keyword), start)
+                    .with_help(format!("`{}` can only be used inside a `while` or `for` loop", keyword)),
+            );
+            return Ok(Node::new(ASTNode::Error, span));
+        }
+
+        let node = if token == Token::Break {
+            ASTNode::Break
+        } else {
+            ASTNode::Continue
+        };
+        Ok(Node::new(node, span))
+    }
+
+    /// Parses `def name(param: type, ...) -> type { ... }`
+    ///
+    /// Parameter types and the return type are optional.
+    fn function_definition(&mut self) -> Result<Node, ParseError> {
+        let start = self.current_span;
+        self.eat(Token::Def)?;
+        let name = self.identifier()?;
+
+        let params_start = self.current_span;
+        self.eat(Token::LParen)?;
+        let mut params: Vec<Parameter> = Vec::new();
+        while self.current_token != Token::RParen {
+            let param_start = self.current_span;
+            let param_name = self.identifier()?;
+            let param_type = if self.current_token == Token::Colon {
+                self.eat(Token::Colon)?;
+                Some(self.parse_type()?)
+            } else {
+                None
+            };
+            let param_span = self.span_from(param_start);
+
+            if let Some(previous) = params.iter().find(|p| p.name == param_name) {
+                let error = ParseError::new(
+                    "E0109",
+                    format!("Parameter '{}' is declared more than once", param_name),
+                    param_span,
+                )
+                .with_label(previous.span, "first declared here");
+                self.errors.push(error);
+            }
+            params.push(Parameter {
+                name: param_name,
+                param_type,
+                span: param_span,
+            });
+
+            if self.current_token != Token::Comma {
+                break;
+            }
+            self.eat(Token::Comma)?;
+        }
+        self.eat(Token::RParen)
+            .map_err(|e| e.with_label(params_start, "parameter list opened here"))?;
+
+        let return_type = if self.current_token == Token::Arrow {
+            self.eat(Token::Arrow)?;
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+
+        // Loops outside the function don't make `break` valid inside it
+        let enclosing_loops = std::mem::replace(&mut self.loop_depth, 0);
+        self.function_depth += 1;
+        let body = self.block();
+        self.function_depth -= 1;
+        self.loop_depth = enclosing_loops;
+        let body = body?;
+
+        let function = Function {
+            name,
+            params,
+            return_type,
+            body,
+            overflow_mode: self.overflow_mode,
+        };
+        Ok(Node::new(ASTNode::FunctionDef(Rc::new(function)), self.span_from(start)))
+    }
+
+    /// Parses `return;` or `return expr;`
+    ///
+    /// Using `return` outside a function is recorded as an error, but the
+    /// statement is still consumed so parsing continues normally.
+    fn return_statement(&mut self) -> Result<Node, ParseError> {
+        let start = self.current_span;
+        self.eat(Token::Return)?;
+        let value = if self.at_statement_end() {
+            None
+        } else {
+            Some(Box::new(self.expression()?))
+        };
+        self.end_statement()?;
+        let span = self.span_from(start);
+
+        if self.function_depth == 0 {
+            self.errors.push(
+                ParseError::new("E0108", "`return` outside of a function", start)
+                    .with_help("`return` can only be used inside a `def` body"),
+            );
+            return Ok(Node::new(ASTNode::Error, span));
+        }
+        Ok(Node::new(ASTNode::Return(value), span))
     }
 
-    fn print_statement(&mut self) -> Result<ASTNode, String> {
+    /// Parses `wrapping { ... }` or `saturating { ... }`
+    fn overflow_block(&mut self) -> Result<Node, ParseError> {
+        let start = self.current_span;
+        let mode = if self.current_token == Token::Wrapping {
+            OverflowMode::Wrapping
+        } else {
+            OverflowMode::Saturating
+        };
+        let token = self.current_token.clone();
+        self.eat(token)?;
+
+        let enclosing_mode = std::mem::replace(&mut self.overflow_mode, mode);
+        let body = self.block();
+        self.overflow_mode = enclosing_mode;
+        let body = body?;
+
+        Ok(Node::new(ASTNode::OverflowBlock(mode, Box::new(body)), self.span_from(start)))
+    }
+
+    /// Parses a parenthesized, comma-separated argument list
+    fn call_arguments(&mut self) -> Result<Vec<Node>, ParseError> {
+        let start = self.current_span;
+        self.eat(Token::LParen)?;
+        let mut arguments = Vec::new();
+        while self.current_token != Token::RParen {
+            arguments.push(self.expression()?);
+            if self.current_token != Token::Comma {
+                break;
+            }
+            self.eat(Token::Comma)?;
+        }
+        self.eat(Token::RParen)
+            .map_err(|e| e.with_label(start, "argument list opened here"))?;
+        Ok(arguments)
+    }
+
+    /// Parses a block of statements, either brace-delimited or, in
+    /// indentation mode, a `:` followed by an indented body
+    ///
+    /// Errors inside the block are recovered from so that a single mistake
+    /// does not hide errors in the rest of the block.
+    fn block(&mut self) -> Result<Node, ParseError> {
+        if self.current_token == Token::Colon {
+            return self.indented_block();
+        }
+        self.skip_newline_before(&[Token::LBrace])?;
+        let start = self.current_span;
+        self.eat(Token::LBrace)?;
+
+        let statements = self.statement_list(|token| *token == Token::RBrace);
+        self.eat(Token::RBrace)
+            .map_err(|e| e.with_label(start, "block opened here"))?;
+
+        Ok(Node::new(ASTNode::Block(statements), self.span_from(start)))
+    }
+
+    /// Parses `:` followed by a line break and an indented body
+    fn indented_block(&mut self) -> Result<Node, ParseError> {
+        let start = self.current_span;
+        self.eat(Token::Colon)?;
+        if self.current_token == Token::Newline {
+            self.advance()?;
+        }
+        if self.current_token != Token::Indent {
+            return Err(self
+                .error_here("E0111", "Expected an indented block")
+                .with_label(start, "block opened here")
+                .with_help(format!(
+                    "indent the body on the following lines; indented blocks need a `{}` first line or a .{} file",
+                    INDENT_PRAGMA, INDENT_EXTENSION
+                )));
+        }
+        let statements = self.indented_statements()?;
+        Ok(Node::new(ASTNode::Block(statements), self.span_from(start)))
+    }
+
+    /// Parses the statements between an INDENT and its matching DEDENT
+    fn indented_statements(&mut self) -> Result<Vec<Node>, ParseError> {
+        self.eat(Token::Indent)?;
+        let statements = self.statement_list(|token| *token == Token::Dedent);
+        if self.current_token == Token::Dedent {
+            self.advance()?;
+        }
+        Ok(statements)
+    }
+
+    /// Reports lines indented without an enclosing block, parsing them so
+    /// their indentation doesn't confuse the blocks around them
+    fn unexpected_indentation(&mut self) -> Result<Node, ParseError> {
+        let start = self.current_span;
+        self.errors.push(
+            self.error_here("E0110", "Unexpected indentation")
+                .with_help("only the body of a block opened with `:` is indented"),
+        );
+        self.indented_statements()?;
+        Ok(Node::new(ASTNode::Error, self.span_from(start)))
+    }
+
+    fn print_statement(&mut self) -> Result<Node, ParseError> {
+        let start = self.current_span;
         self.eat(Token::Print)?;
-        let expr = self.expr()?;
-        self.eat(Token::Semicolon)?;
-        Ok(ASTNode::Print(Box::new(expr)))
+        let expr = self.expression()?;
+        self.end_statement()?;
+        Ok(Node::new(ASTNode::Print(Box::new(expr)), self.span_from(start)))
     }
 
-    fn expression_statement(&mut self) -> Result<ASTNode, String> {
-        let expr = self.expr()?;
-        self.eat(Token::Semicolon)?;
+    fn expression_statement(&mut self) -> Result<Node, ParseError> {
+        let expr = self.expression()?;
+        self.end_statement()?;
         Ok(expr)
     }
 
-    /// Parses an expression
+    /// Parses a full expression
+    ///
+    /// This is the lowest precedence level: `or`, then `and`, then `not`,
+    /// then comparisons, then arithmetic via expr().
+    ///
+    /// # Returns
+    /// * `Result<Node, ParseError>` - The parsed expression or an error
+    fn expression(&mut self) -> Result<Node, ParseError> {
+        self.logical_or()
+    }
+
+    /// Parses a chain of `or` operations
+    fn logical_or(&mut self) -> Result<Node, ParseError> {
+        let mut node = self.logical_and()?;
+
+        while self.current_token == Token::Or {
+            self.eat(Token::Or)?;
+            let right = self.logical_and()?;
+            let span = node.span.to(right.span);
+            node = Node::new(ASTNode::LogicalOp(Box::new(node), Token::Or, Box::new(right)), span);
+        }
+
+        Ok(node)
+    }
+
+    /// Parses a chain of `and` operations
+    fn logical_and(&mut self) -> Result<Node, ParseError> {
+        let mut node = self.logical_not()?;
+
+        while self.current_token == Token::And {
+            self.eat(Token::And)?;
+            let right = self.logical_not()?;
+            let span = node.span.to(right.span);
+            node = Node::new(ASTNode::LogicalOp(Box::new(node), Token::And, Box::new(right)), span);
+        }
+
+        Ok(node)
+    }
+
+    /// Parses an optional `not` prefix
+    fn logical_not(&mut self) -> Result<Node, ParseError> {
+        if self.current_token == Token::Not {
+            let start = self.current_span;
+            self.eat(Token::Not)?;
+            let operand = self.logical_not()?;
+            let span = start.to(operand.span);
+            return Ok(Node::new(ASTNode::UnaryOp(Token::Not, Box::new(operand)), span));
+        }
+        self.comparison()
+    }
+
+    /// Parses a single comparison between two arithmetic expressions
+    ///
+    /// Comparisons do not chain: `a < b < c` is rejected.
+    fn comparison(&mut self) -> Result<Node, ParseError> {
+        let node = self.expr()?;
+        if !is_comparison(&self.current_token) {
+            return Ok(node);
+        }
+
+        let token = self.current_token.clone();
+        self.eat(token.clone())?;
+        let right = self.expr()?;
+        if is_comparison(&self.current_token) {
+            return Err(self
+                .error_here("E0106", "Comparison operators cannot be chained")
+                .with_help("combine the comparisons with `and`"));
+        }
+        let span = node.span.to(right.span);
+        Ok(Node::new(ASTNode::BinaryOp(Box::new(node), token, Box::new(right)), span))
+    }
+
+    /// Parses an arithmetic expression
     ///
     /// Handles addition and subtraction operations, delegating to term()
     /// for higher precedence operations.
     ///
     /// # Returns
-    /// * `Result<ASTNode, String>` - The parsed expression or an error
-    fn expr(&mut self) -> Result<ASTNode, String> {
+    /// * `Result<Node, ParseError>` - The parsed expression or an error
+    fn expr(&mut self) -> Result<Node, ParseError> {
         let mut node = self.term()?;
 
         while matches!(self.current_token, Token::Plus | Token::Minus) {
             let token = self.current_token.clone();
             self.eat(token.clone())?;
-            node = ASTNode::BinaryOp(Box::new(node), token, Box::new(self.term()?));
+            let right = self.term()?;
+            let span = node.span.to(right.span);
+            node = Node::new(ASTNode::BinaryOp(Box::new(node), token, Box::new(right)), span);
         }
 
         Ok(node)
@@ -248,14 +960,16 @@ impl<'a> Parser<'a> {
     /// for higher precedence operations.
     ///
     /// # Returns
-    /// * `Result<ASTNode, String>` - The parsed term or an error
-    fn term(&mut self) -> Result<ASTNode, String> {
+    /// * `Result<Node, ParseError>` - The parsed term or an error
+    fn term(&mut self) -> Result<Node, ParseError> {
         let mut node = self.factor()?;
 
         while matches!(self.current_token, Token::Asterisk | Token::Slash) {
             let token = self.current_token.clone();
             self.eat(token.clone())?;
-            node = ASTNode::BinaryOp(Box::new(node), token, Box::new(self.factor()?));
+            let right = self.factor()?;
+            let span = node.span.to(right.span);
+            node = Node::new(ASTNode::BinaryOp(Box::new(node), token, Box::new(right)), span);
         }
 
         Ok(node)
@@ -267,38 +981,111 @@ impl<'a> Parser<'a> {
     /// and parenthesized expressions.
     ///
     /// # Returns
-    /// * `Result<ASTNode, String>` - The parsed factor or an error
-    fn factor(&mut self) -> Result<ASTNode, String> {
-        match &self.current_token {
+    /// * `Result<Node, ParseError>` - The parsed factor or an error
+    fn factor(&mut self) -> Result<Node, ParseError> {
+        let start = self.current_span;
+        let node = match &self.current_token {
             Token::Number(value) => {
                 let value = *value;
                 self.eat(Token::Number(value))?;
-                Ok(ASTNode::Number(value))
+                ASTNode::Number(value)
+            },
+            Token::Float(value) => {
+                let value = *value;
+                self.eat(Token::Float(value))?;
+                ASTNode::Float(value)
             },
             Token::StringLiteral(text) => {
                 let text = text.clone();
                 self.eat(Token::StringLiteral(text.clone()))?;
-                Ok(ASTNode::StringLiteral(text))
+                ASTNode::StringLiteral(text)
+            },
+            Token::CharLiteral(c) => {
+                let c = *c;
+                self.eat(Token::CharLiteral(c))?;
+                ASTNode::CharLiteral(c)
+            },
+            Token::True => {
+                self.eat(Token::True)?;
+                ASTNode::Bool(true)
+            },
+            Token::False => {
+                self.eat(Token::False)?;
+                ASTNode::Bool(false)
             },
             Token::Identifier(ref name) => {
                 let name = name.clone();
                 self.eat(Token::Identifier(name.clone()))?;
-                Ok(ASTNode::Identifier(name))
+                if self.current_token == Token::LParen {
+                    ASTNode::Call(name, self.call_arguments()?)
+                } else {
+                    ASTNode::Identifier(name)
+                }
+            },
+            token if is_type(token) => {
+                let target = self.parse_type()?;
+                let paren = self.current_span;
+                self.eat(Token::LParen)
+                    .map_err(|e| e.with_help("a type name is only valid as a cast here, e.g. `int32(x)`"))?;
+                let value = self.expression()?;
+                self.eat(Token::RParen)
+                    .map_err(|e| e.with_label(paren, "unclosed delimiter opened here"))?;
+                ASTNode::Cast(target, Box::new(value))
             },
             Token::LParen => {
                 self.eat(Token::LParen)?;
-                let node = self.expr()?;
-                self.eat(Token::RParen)?;
-                Ok(node)
+                let node = self.expression()?;
+                self.eat(Token::RParen)
+                    .map_err(|e| e.with_label(start, "unclosed delimiter opened here"))?;
+                // Widen the span so it includes the parentheses
+                return Ok(Node::new(node.node, self.span_from(start)));
             },
-            _ => Err(format!("Unexpected token: {:?}", self.current_token)),
-        }
+            _ => {
+                return Err(self
+                    .error_here("E0104", format!("Unexpected token: {:?}", self.current_token))
+                    .with_help("expected a number, string, variable or `(`"))
+            }
+        };
+        Ok(Node::new(node, self.span_from(start)))
     }
 }
 
+/// Returns true for tokens that name a type
+fn is_type(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::TypeInt
+            | Token::TypeInt32
+            | Token::TypeInt64
+            | Token::TypeFloat
+            | Token::TypeFloat32
+            | Token::TypeFloat64
+            | Token::TypeStr
+            | Token::TypeStr8
+            | Token::TypeStr32
+            | Token::TypeStr64
+            | Token::TypeChar
+            | Token::TypeBool
+    )
+}
+
+/// Returns true for tokens that compare two values
+fn is_comparison(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::EqualEqual
+            | Token::NotEqual
+            | Token::Less
+            | Token::LessEqual
+            | Token::Greater
+            | Token::GreaterEqual
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::diagnostics::{Diagnostic, Label};
 
     #[test]
     fn test_arithmetic_expression() {
@@ -306,7 +1093,7 @@ mod tests {
         let lexer = Lexer::new(input);
         let mut parser = Parser::new(lexer);
         let result = parser.parse().unwrap();
-        assert!(matches!(result[0], ASTNode::BinaryOp(..)));
+        assert!(matches!(result[0].node, ASTNode::BinaryOp(..)));
     }
 
     #[test]
@@ -315,14 +1102,426 @@ mod tests {
         let lexer = Lexer::new(input);
         let mut parser = Parser::new(lexer);
         let result = parser.parse().unwrap();
+        match &result[0].node {
+            ASTNode::TypedVarAssign(name, var_type, value) => {
+                assert_eq!(name, "x");
+                assert_eq!(*var_type, Type::Int);
+                assert_eq!(value.node, ASTNode::Number(42));
+            }
+            other => panic!("Expected typed declaration, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_node_spans() {
+        let input = "x: int = 42;\ny = (1 + 2) * x;";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let result = parser.parse().unwrap();
+        assert_eq!(result[0].span, Span::new(0, 12, 1, 1));
+        assert_eq!(result[1].span, Span::new(13, 29, 2, 1));
+        match &result[1].node {
+            ASTNode::VarAssign(_, value) => {
+                assert_eq!(&input[value.span.start..value.span.end], "(1 + 2) * x");
+            }
+            other => panic!("Expected assignment, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_lexer_error_is_reported() {
+        let input = "x: int = 42;\ny: int = 4 @ 2;";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let errors = parser.parse().unwrap_err();
+        let error = &errors[0];
+        assert_eq!(error.message, "Unexpected character: '@'");
+        assert_eq!(error.span, Span::new(24, 25, 2, 12));
+    }
+
+    #[test]
+    fn test_unclosed_paren_diagnostic() {
+        let input = "x = (1 + 2;";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let errors = parser.parse().unwrap_err();
+        let error = &errors[0];
+        assert_eq!(error.code, "E0101");
+        assert_eq!(error.labels, vec![Label::new(Span::new(4, 5, 1, 5), "unclosed delimiter opened here")]);
+        let diagnostic = Diagnostic::from(error);
+        assert_eq!(diagnostic.secondary.len(), 1);
+    }
+
+    #[test]
+    fn test_logical_precedence() {
+        let input = "not a == 1 or b < 2 + 3 and c;";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let result = parser.parse().unwrap();
+        // Parsed as (not (a == 1)) or ((b < (2 + 3)) and c)
+        match &result[0].node {
+            ASTNode::LogicalOp(left, Token::Or, right) => {
+                match &left.node {
+                    ASTNode::UnaryOp(Token::Not, operand) => {
+                        assert!(matches!(operand.node, ASTNode::BinaryOp(_, Token::EqualEqual, _)));
+                    }
+                    other => panic!("Expected not, found {:?}", other),
+                }
+                match &right.node {
+                    ASTNode::LogicalOp(comparison, Token::And, _) => match &comparison.node {
+                        ASTNode::BinaryOp(_, Token::Less, sum) => {
+                            assert!(matches!(sum.node, ASTNode::BinaryOp(_, Token::Plus, _)));
+                        }
+                        other => panic!("Expected comparison, found {:?}", other),
+                    },
+                    other => panic!("Expected and, found {:?}", other),
+                }
+            }
+            other => panic!("Expected or, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_chained_comparison_is_rejected() {
+        let input = "1 < 2 < 3;";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let errors = parser.parse().unwrap_err();
+        assert_eq!(errors[0].code, "E0106");
+    }
+
+    #[test]
+    fn test_if_elif_else() {
+        let input = "
+            if x { print 1; } elif y { print 2; } else { print 3; print 4; }
+            print 5;
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let result = parser.parse().unwrap();
+        assert_eq!(result.len(), 2);
+        match &result[0].node {
+            ASTNode::If(condition, then_branch, Some(elif)) => {
+                assert_eq!(condition.node, ASTNode::Identifier("x".to_string()));
+                assert!(matches!(&then_branch.node, ASTNode::Block(body) if body.len() == 1));
+                match &elif.node {
+                    ASTNode::If(_, _, Some(else_branch)) => {
+                        assert!(matches!(&else_branch.node, ASTNode::Block(body) if body.len() == 2));
+                    }
+                    other => panic!("Expected elif, found {:?}", other),
+                }
+            }
+            other => panic!("Expected if statement, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_while_loop() {
+        let input = "while i < 10 { if i == 5 { break; } continue; }";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let result = parser.parse().unwrap();
+        match &result[0].node {
+            ASTNode::While(condition, body) => {
+                assert!(matches!(condition.node, ASTNode::BinaryOp(_, Token::Less, _)));
+                match &body.node {
+                    ASTNode::Block(statements) => {
+                        assert!(matches!(statements[0].node, ASTNode::If(..)));
+                        assert_eq!(statements[1].node, ASTNode::Continue);
+                    }
+                    other => panic!("Expected block, found {:?}", other),
+                }
+            }
+            other => panic!("Expected while loop, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_loop_control_outside_loop() {
+        let input = "break; if x { continue; } while x { break; } y = 1;";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let (statements, errors) = parser.parse_with_recovery();
+        let messages: Vec<_> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, vec!["`break` outside of a loop", "`continue` outside of a loop"]);
+        assert_eq!(errors[0].span, Span::new(0, 5, 1, 1));
         assert_eq!(
-            result[0],
-            ASTNode::TypedVarAssign(
-                "x".to_string(),
-                RuspyType::Int(0),
-                Box::new(ASTNode::Number(42))
-            )
+            errors[0].help.as_deref(),
+            Some("`break` can only be used inside a `while` or `for` loop")
         );
+        assert_eq!(statements.len(), 4);
+    }
+
+    #[test]
+    fn test_for_loop() {
+        let input = "for i in range(0, 10, 2) { if i == 4 { continue; } print i; }";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let result = parser.parse().unwrap();
+        match &result[0].node {
+            ASTNode::For(name, iterable, body) => {
+                assert_eq!(name, "i");
+                assert!(matches!(&iterable.node, ASTNode::Call(f, args) if f == "range" && args.len() == 3));
+                assert!(matches!(&body.node, ASTNode::Block(statements) if statements.len() == 2));
+            }
+            other => panic!("Expected for loop, found {:?}", other),
+        }
+
+        let lexer = Lexer::new("for 1 in x {}");
+        let mut parser = Parser::new(lexer);
+        assert_eq!(parser.parse().unwrap_err()[0].code, "E0102");
+    }
+
+    #[test]
+    fn test_function_definition() {
+        let input = "def add(a: int, b) -> int { return a + b; } add(1, 2 * 3);";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let result = parser.parse().unwrap();
+        match &result[0].node {
+            ASTNode::FunctionDef(function) => {
+                assert_eq!(function.name, "add");
+                assert_eq!(function.params.len(), 2);
+                assert_eq!(function.params[0].param_type, Some(Type::Int));
+                assert_eq!(function.params[1].param_type, None);
+                assert_eq!(function.params[1].span, Span::new(16, 17, 1, 17));
+                assert_eq!(function.return_type, Some(Type::Int));
+                assert!(matches!(&function.body.node, ASTNode::Block(body) if body.len() == 1));
+            }
+            other => panic!("Expected function definition, found {:?}", other),
+        }
+        match &result[1].node {
+            ASTNode::Call(name, arguments) => {
+                assert_eq!(name, "add");
+                assert_eq!(arguments.len(), 2);
+                assert!(matches!(arguments[1].node, ASTNode::BinaryOp(..)));
+            }
+            other => panic!("Expected call, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_overflow_blocks() {
+        let input = "wrapping { def f() { return 1 } } def g() { return 2 } saturating { x = 1 }";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let result = parser.parse().unwrap();
+        match &result[0].node {
+            ASTNode::OverflowBlock(OverflowMode::Wrapping, body) => match &body.node {
+                ASTNode::Block(statements) => {
+                    assert!(matches!(&statements[0].node, ASTNode::FunctionDef(f)
+                        if f.overflow_mode == OverflowMode::Wrapping));
+                }
+                other => panic!("Expected block, found {:?}", other),
+            },
+            other => panic!("Expected wrapping block, found {:?}", other),
+        }
+        assert!(matches!(&result[1].node, ASTNode::FunctionDef(f)
+            if f.overflow_mode == OverflowMode::Checked));
+        assert!(matches!(result[2].node, ASTNode::OverflowBlock(OverflowMode::Saturating, _)));
+    }
+
+    #[test]
+    fn test_casts() {
+        let lexer = Lexer::new("x = float32(1 + 2) * 2");
+        let mut parser = Parser::new(lexer);
+        let result = parser.parse().unwrap();
+        match &result[0].node {
+            ASTNode::VarAssign(_, value) => match &value.node {
+                ASTNode::BinaryOp(left, Token::Asterisk, _) => {
+                    assert!(matches!(&left.node, ASTNode::Cast(Type::Float32, inner)
+                        if matches!(inner.node, ASTNode::BinaryOp(..))));
+                    assert_eq!((left.span.start, left.span.end), (4, 18));
+                }
+                other => panic!("Expected multiplication, found {:?}", other),
+            },
+            other => panic!("Expected assignment, found {:?}", other),
+        }
+
+        let lexer = Lexer::new("x = int64 + 1");
+        let mut parser = Parser::new(lexer);
+        let errors = parser.parse().unwrap_err();
+        assert_eq!(errors[0].code, "E0101");
+        assert!(errors[0].help.as_deref().unwrap().contains("`int32(x)`"));
+    }
+
+    #[test]
+    fn test_function_errors() {
+        let input = "
+            return 1;
+            def f(a, a) { while a { def g() { break; } } }
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let errors = parser.parse().unwrap_err();
+        let codes: Vec<_> = errors.iter().map(|e| e.code).collect();
+        assert_eq!(codes, vec!["E0108", "E0109", "E0107"]);
+    }
+
+    #[test]
+    fn test_indented_blocks() {
+        let input = "
+def sign(x: int64) -> int64:
+    if x > 0:
+        return 1
+    elif x < 0:
+        return 0 - 1
+    else:
+        return 0
+
+for i in range(0, 3):
+    print sign(i); print i
+";
+        let lexer = Lexer::with_indentation(input);
+        let mut parser = Parser::new(lexer);
+        let result = parser.parse().unwrap();
+        assert_eq!(result.len(), 2);
+        match &result[0].node {
+            ASTNode::FunctionDef(function) => match &function.body.node {
+                ASTNode::Block(body) => {
+                    assert_eq!(body.len(), 1);
+                    assert!(matches!(&body[0].node, ASTNode::If(_, _, Some(_))));
+                }
+                other => panic!("Expected block, found {:?}", other),
+            },
+            other => panic!("Expected function definition, found {:?}", other),
+        }
+        assert!(matches!(&result[1].node, ASTNode::For(_, _, body)
+            if matches!(&body.node, ASTNode::Block(statements) if statements.len() == 2)));
+    }
+
+    #[test]
+    fn test_indentation_errors() {
+        let input = "x = 1\n    y = 2\nif x:\nprint x\n";
+        let lexer = Lexer::with_indentation(input);
+        let mut parser = Parser::new(lexer);
+        let errors = parser.parse().unwrap_err();
+        let codes: Vec<_> = errors.iter().map(|e| e.code).collect();
+        assert_eq!(codes, vec!["E0110", "E0111"]);
+
+        // Without indentation mode a `:` block is reported the same way
+        let lexer = Lexer::new("if x:\n    print x;\n");
+        let mut parser = Parser::new(lexer);
+        assert_eq!(parser.parse().unwrap_err()[0].code, "E0111");
+    }
+
+    #[test]
+    fn test_errors_inside_blocks() {
+        let input = "if x { y = ; print 1; } }";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let (statements, errors) = parser.parse_with_recovery();
+        let codes: Vec<_> = errors.iter().map(|e| e.code).collect();
+        assert_eq!(codes, vec!["E0104", "E0105"]);
+        match &statements[0].node {
+            ASTNode::If(_, then_branch, None) => match &then_branch.node {
+                ASTNode::Block(body) => {
+                    assert_eq!(body[0].node, ASTNode::Error);
+                    assert!(matches!(body[1].node, ASTNode::Print(_)));
+                }
+                other => panic!("Expected block, found {:?}", other),
+            },
+            other => panic!("Expected if statement, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_recovers_from_broken_block_headers() {
+        let cases = [
+            ("if x == {\n    y = 1;\n}\nprint 2", "E0104"),
+            ("if x == {\n    y = 1\n} else {\n    y = 2\n}\nprint 2", "E0104"),
+            ("while (x {\n    if x { print x }\n}\nprint 2", "E0101"),
+            ("def f(a: int64 {\n    return a;\n}\nprint 2", "E0101"),
+            ("for in range(0, 3) {\n    print 1\n}\nprint 2", "E0102"),
+        ];
+        for (input, code) in cases {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let (statements, errors) = parser.parse_with_recovery();
+            let codes: Vec<_> = errors.iter().map(|e| e.code).collect();
+            assert_eq!(codes, vec![code], "{}", input);
+            assert!(matches!(statements.last().unwrap().node, ASTNode::Print(_)), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_unclosed_block() {
+        let input = "if x { print 1;";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let errors = parser.parse().unwrap_err();
+        assert_eq!(errors[0].labels, vec![Label::new(Span::new(5, 6, 1, 6), "block opened here")]);
+    }
+
+    #[test]
+    fn test_error_location() {
+        let input = "x: int = 42;\ny: int = 1 +;";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let errors = parser.parse().unwrap_err();
+        assert_eq!(errors[0].span.line, 2);
+        assert_eq!(errors[0].span.column, 13);
+    }
+
+    #[test]
+    fn test_reports_all_errors() {
+        let input = "
+            x: int = 1 +;
+            y: nope = 2;
+            print 3 4
+            print x;
+            z = (4;
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let (statements, errors) = parser.parse_with_recovery();
+        let codes: Vec<_> = errors.iter().map(|e| e.code).collect();
+        assert_eq!(codes, vec!["E0104", "E0103", "E0101", "E0101"]);
+        assert_eq!(statements.len(), 5);
+        assert_eq!(statements[0].node, ASTNode::Error);
+        assert_eq!(statements[1].node, ASTNode::Error);
+        assert_eq!(statements[2].node, ASTNode::Error);
+        assert!(matches!(statements[3].node, ASTNode::Print(_)));
+        assert_eq!(statements[4].node, ASTNode::Error);
+    }
+
+    #[test]
+    fn test_recovers_from_lexer_errors() {
+        let input = "a = 1 @ 2; b = $; c = 3;";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let (statements, errors) = parser.parse_with_recovery();
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|e| e.code == "E0001"));
+        assert!(matches!(statements.last().unwrap().node, ASTNode::VarAssign(..)));
+
+        // A lexer error is reported once, not again by the statement it interrupted
+        let cases = [
+            ("print \"\\q\"", "E0008"),
+            ("print 'ab'", "E0007"),
+            ("print 9223372036854775808;", "E0003"),
+            ("print @\nprint 1", "E0001"),
+            ("x = \"open\ny = 1", "E0002"),
+        ];
+        for (input, code) in cases {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let errors = parser.parse().unwrap_err();
+            let codes: Vec<_> = errors.iter().map(|e| e.code).collect();
+            assert_eq!(codes, vec![code], "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_float_declaration() {
+        let input = "x: float = 3.25;";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let result = parser.parse().unwrap();
+        match &result[0].node {
+            ASTNode::TypedVarAssign(_, _, value) => assert_eq!(value.node, ASTNode::Float(3.25)),
+            other => panic!("Expected typed declaration, found {:?}", other),
+        }
     }
 
     #[test]
@@ -339,7 +1538,7 @@ mod tests {
         let lexer = Lexer::new(input);
         let mut parser = Parser::new(lexer);
         let result = parser.parse().unwrap();
-        assert!(matches!(result[0], ASTNode::BinaryOp(..)));
+        assert!(matches!(result[0].node, ASTNode::BinaryOp(..)));
     }
 
     #[test]
@@ -362,4 +1561,34 @@ mod tests {
         let result = parser.parse().unwrap();
         assert_eq!(result.len(), 1);
     }
+
+    #[test]
+    fn test_optional_semicolons() {
+        let input = "
+            x = 1; y = 2
+            total = x +
+                y
+            def f(a) { return a }
+            if total > 2 {
+                print f(total)
+            }
+            else
+            {
+                print 0
+            }
+        ";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let result = parser.parse().unwrap();
+        assert_eq!(result.len(), 5);
+        assert!(matches!(&result[2].node, ASTNode::VarAssign(_, value)
+            if matches!(value.node, ASTNode::BinaryOp(..))));
+        assert!(matches!(result[4].node, ASTNode::If(_, _, Some(_))));
+
+        let lexer = Lexer::new("x = 1 y = 2");
+        let mut parser = Parser::new(lexer);
+        let errors = parser.parse().unwrap_err();
+        assert_eq!(errors[0].code, "E0101");
+        assert_eq!(errors[0].labels[0].message, "expected `;` or a line break after this");
+    }
 }
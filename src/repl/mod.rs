@@ -0,0 +1,340 @@
+/// REPL module for the Ruspy language
+///
+/// Provides an interactive read-eval-print loop. A single interpreter is kept
+/// alive for the whole session so variables persist between inputs.
+use crate::diagnostics::{Diagnostic, Renderer};
+use crate::interpreter::{Interpreter, SharedOutput};
+use crate::lexer::Lexer;
+use crate::parser::{ParseError, Parser};
+use crate::semantic;
+use std::io::{self, BufRead, Write};
+
+/// Name shown in diagnostics for code typed at the prompt
+const REPL_FILE: &str = "<repl>";
+
+const PROMPT: &str = ">>> ";
+const CONTINUATION_PROMPT: &str = "... ";
+
+const HELP: &str = "\
+Enter statements to run them; bare expressions print their value.
+Input continues on the next line while a statement is incomplete;
+an empty line runs it anyway.
+
+Commands:
+  :help          show this message
+  :vars          list defined variables
+  :type <expr>   show the type of an expression without running it
+  :ast <code>    show the syntax tree of some code
+  :history       list previous inputs
+  :quit          leave the REPL";
+
+/// Interactive session state
+pub struct Repl<W: Write + 'static> {
+    /// Interpreter shared by every input in the session
+    interpreter: Interpreter,
+    /// Inputs entered so far, oldest first
+    history: Vec<String>,
+    /// Code of every input that parsed, in order, for static queries such as `:type`
+    session: String,
+    /// Where prompts, results, errors and `print` output are written
+    output: SharedOutput<W>,
+    /// Whether diagnostics are rendered with colour
+    color: bool,
+}
+
+impl<W: Write + 'static> Repl<W> {
+    /// Creates a new session writing to `output`
+    pub fn new(output: W, color: bool) -> Self {
+        let output = SharedOutput::new(output);
+        Repl {
+            interpreter: Interpreter::with_output(Box::new(output.clone())),
+            history: Vec::new(),
+            session: String::new(),
+            output,
+            color,
+        }
+    }
+
+    /// Reads and evaluates input until EOF or `:quit`
+    ///
+    /// # Errors
+    /// * When reading input or writing output fails
+    pub fn run<R: BufRead>(&mut self, mut input: R) -> io::Result<()> {
+        let mut buffer = String::new();
+        loop {
+            let prompt = if buffer.is_empty() { PROMPT } else { CONTINUATION_PROMPT };
+            write!(self.output, "{}", prompt)?;
+            self.output.flush()?;
+
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                writeln!(self.output)?;
+                return Ok(());
+            }
+
+            if buffer.is_empty() {
+                let trimmed = line.trim();
+                if trimmed.is_empty() {
+                    continue;
+                }
+                if let Some(command) = trimmed.strip_prefix(':') {
+                    self.history.push(trimmed.to_string());
+                    if !self.command(command)? {
+                        return Ok(());
+                    }
+                    continue;
+                }
+            }
+
+            // An empty continuation line runs whatever has been entered so far
+            let force = !buffer.is_empty() && line.trim().is_empty();
+            buffer.push_str(&line);
+            if self.execute(&buffer, force)? {
+                self.history.push(buffer.trim_end().to_string());
+                buffer.clear();
+            }
+        }
+    }
+
+    /// Parses and runs `source`
+    ///
+    /// # Returns
+    /// * false if the input is incomplete and `force` is not set, meaning
+    ///   more lines should be read before running it
+    fn execute(&mut self, source: &str, force: bool) -> io::Result<bool> {
+        let lexer = Lexer::new(source);
+        let mut parser = Parser::new(lexer);
+        let (statements, errors) = parser.parse_with_recovery();
+
+        if !errors.is_empty() {
+            // Later errors can be knock-on effects of the first, so only it decides
+            if !force && is_incomplete(&errors[0], source) {
+                return Ok(false);
+            }
+            for error in &errors {
+                self.report(&Diagnostic::from(error), source)?;
+            }
+            return Ok(true);
+        }
+
+        self.session.push_str(source);
+        if !self.session.ends_with('\n') {
+            self.session.push('\n');
+        }
+        for statement in statements {
+            let echo = statement.node.is_expression();
+            match self.interpreter.interpret(vec![statement]) {
+                Ok(value) if echo => writeln!(self.output, "{}", value)?,
+                Ok(_) => {}
+                Err(error) => {
+                    self.report(&Diagnostic::from(&error), source)?;
+                    break;
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Runs a meta-command (without its leading `:`)
+    ///
+    /// # Returns
+    /// * false if the session should end
+    fn command(&mut self, command: &str) -> io::Result<bool> {
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+
+        match name {
+            "help" => writeln!(self.output, "{}", HELP)?,
+            "quit" | "exit" => return Ok(false),
+            "vars" => {
+                let lines: Vec<_> = self
+                    .interpreter
+                    .variables()
+                    .into_iter()
+                    .map(|(name, value)| format!("{}: {} = {}", name, value.type_name(), value))
+                    .collect();
+                for line in lines {
+                    writeln!(self.output, "{}", line)?;
+                }
+            }
+            "history" => {
+                let lines: Vec<_> = self
+                    .history
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry)| format!("{:>4}  {}", i + 1, entry))
+                    .collect();
+                for line in lines {
+                    writeln!(self.output, "{}", line)?;
+                }
+            }
+            "type" => self.show_type(argument)?,
+            "ast" => self.show_ast(argument)?,
+            _ => writeln!(self.output, "Unknown command ':{}', try :help", name)?,
+        }
+        Ok(true)
+    }
+
+    /// Prints the static type of a single expression without running it
+    ///
+    /// The expression is analysed after the code entered so far in the
+    /// session, with the same inference `ruspy check` uses; `unknown` means
+    /// the type can only be known while the program runs.
+    fn show_type(&mut self, expression: &str) -> io::Result<()> {
+        let source = format!("{};", expression);
+        let lexer = Lexer::new(&source);
+        let mut parser = Parser::new(lexer);
+        match parser.parse() {
+            Ok(statements) if statements.len() == 1 && statements[0].node.is_expression() => {}
+            Ok(_) => return writeln!(self.output, ":type expects a single expression"),
+            Err(errors) => {
+                for error in &errors {
+                    self.report(&Diagnostic::from(error), &source)?;
+                }
+                return Ok(());
+            }
+        }
+
+        // Every session input parsed on its own, so the combined program does too
+        let program_source = format!("{}{}", self.session, source);
+        let lexer = Lexer::new(&program_source);
+        let mut parser = Parser::new(lexer);
+        let Ok(program) = parser.parse() else {
+            return writeln!(self.output, "unknown");
+        };
+        let Some(span) = program.last().map(|statement| statement.span) else {
+            return writeln!(self.output, "unknown");
+        };
+        let analysis = semantic::analyze(&program);
+        // Errors in earlier inputs were already reported when they ran
+        let errors: Vec<_> = analysis
+            .errors
+            .iter()
+            .filter(|error| error.span.start >= self.session.len())
+            .collect();
+        if !errors.is_empty() {
+            for error in errors {
+                self.report(&Diagnostic::from(error), &program_source)?;
+            }
+            return Ok(());
+        }
+        match analysis.type_of(span) {
+            Some(ty) => writeln!(self.output, "{}", ty),
+            None => writeln!(self.output, "unknown"),
+        }
+    }
+
+    /// Parses `code` and prints the resulting syntax tree
+    fn show_ast(&mut self, code: &str) -> io::Result<()> {
+        let lexer = Lexer::new(code);
+        let mut parser = Parser::new(lexer);
+        match parser.parse() {
+            Ok(statements) => {
+                for statement in statements {
+                    writeln!(self.output, "{:#?}", statement)?;
+                }
+                Ok(())
+            }
+            Err(errors) => {
+                for error in &errors {
+                    self.report(&Diagnostic::from(error), code)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Renders a diagnostic for the given input
+    fn report(&mut self, diagnostic: &Diagnostic, source: &str) -> io::Result<()> {
+        let renderer = Renderer::new(source, REPL_FILE, self.color);
+        write!(self.output, "{}", renderer.render(diagnostic))
+    }
+}
+
+/// Returns true if `error` was caused by the input ending too early
+fn is_incomplete(error: &ParseError, source: &str) -> bool {
+    // E0002: unterminated string literal; only a triple-quoted one reaches the end of input
+    (error.code == "E0002" && error.span.end == source.len()) || error.span.start >= source.trim_end().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn run(input: &str) -> String {
+        let output = SharedOutput::<Vec<u8>>::default();
+        Repl::new(output.clone(), false).run(Cursor::new(input)).unwrap();
+        output.contents()
+    }
+
+    #[test]
+    fn test_variables_persist() {
+        let output = run("x: int64 = 4;\nx * 2;\n");
+        assert_eq!(output, ">>> >>> 8\n>>> \n");
+    }
+
+    #[test]
+    fn test_print_goes_to_output() {
+        let output = run("print 1\nx = 2\nprint x + 1; x\n");
+        assert_eq!(output, ">>> 1\n>>> >>> 3\n2\n>>> \n");
+    }
+
+    #[test]
+    fn test_multi_line_input() {
+        let output = run("y = (1 +\n2);\ny;\n");
+        assert_eq!(output, ">>> ... >>> 3\n>>> \n");
+    }
+
+    #[test]
+    fn test_multi_line_strings() {
+        let output = run("s = \"\"\"one\ntwo\"\"\"\ns\n");
+        assert_eq!(output, ">>> ... >>> one\ntwo\n>>> \n");
+
+        // A plain string can't continue onto the next line
+        let output = run("print \"one\n");
+        assert!(output.contains("E0002"), "{}", output);
+    }
+
+    #[test]
+    fn test_empty_line_forces_incomplete_input() {
+        let output = run("1 +\n\n");
+        assert!(output.starts_with(">>> ... error[E0104]"));
+    }
+
+    #[test]
+    fn test_meta_commands() {
+        let output = run("a = 2;\nb = \"hi\";\n:vars\n:type a * 3\n:history\n:quit\n1;\n");
+        assert!(output.contains("a: int64 = 2\nb: str = hi\n"));
+        assert!(output.contains("int64\n"));
+        assert!(output.contains("   1  a = 2;\n   2  b = \"hi\";\n   3  :vars\n"));
+        assert!(!output.contains("\n1\n"));
+    }
+
+    #[test]
+    fn test_type_command_does_not_run_code() {
+        let input = "count = 0\ndef f() { print \"SIDE EFFECT\"; return count + 1 }\n:type f()\ncount\n";
+        let output = run(input);
+        assert_eq!(output, ">>> >>> >>> int64\n>>> 0\n>>> \n");
+
+        let output = run("def id(x) { return x }\n:type id(\"a\")\n:type 1 + \"a\"\n:type missing\n");
+        assert!(output.contains(">>> str\n"), "{}", output);
+        assert!(output.contains("error[E0208]"), "{}", output);
+        assert!(output.contains("error[E0301]"), "{}", output);
+    }
+
+    #[test]
+    fn test_ast_command() {
+        let output = run(":ast 1 + 2;\n");
+        assert!(output.contains("BinaryOp("));
+    }
+
+    #[test]
+    fn test_runtime_error_keeps_session() {
+        let output = run("missing;\n5;\n");
+        assert!(output.contains("error[E0301]: Undefined variable: missing in the global scope"));
+        assert!(output.ends_with(">>> 5\n>>> \n"));
+    }
+}
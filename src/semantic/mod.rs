@@ -0,0 +1,851 @@
+/// Semantic analysis for the Ruspy language
+///
+/// Checks a parsed program before it runs: every expression is given a static
+/// type where one can be worked out, identifiers are resolved against the
+/// scopes they are used in, and mismatched typed declarations, undefined
+/// names and invalid operand types are reported. Errors carry the same codes
+/// the interpreter uses for the same problems at run time.
+///
+/// Types are inferred locally: a variable's first assignment fixes its type,
+/// and the types of untyped parameters and results are inferred from the
+/// calls and `return` statements of each function. Values whose type can only
+/// be known while the program runs, such as a parameter that is passed
+/// different types by different calls, are not checked.
+use crate::diagnostics::SourceError;
+use crate::lexer::Span;
+use crate::parser::{ASTNode, Function, Node};
+use crate::types::{ArithmeticOp, Type};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::rc::Rc;
+
+/// Upper bound on the inference rounds run over a program; each round can
+/// only resolve types that depend on the previous round's results
+const MAX_INFERENCE_ROUNDS: usize = 8;
+
+/// Error found by the semantic checker
+pub type SemanticError = SourceError;
+
+/// The parameter and result types of a function, declared or inferred
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    /// Type of each parameter, or None if it can't be known before running
+    pub params: Vec<Option<Type>>,
+    /// Type of the result, or None if it can't be known before running
+    pub return_type: Option<Type>,
+}
+
+impl Signature {
+    /// Returns the signature as a function type, if every part of it is known
+    pub fn as_type(&self) -> Option<Type> {
+        let params = self.params.iter().cloned().collect::<Option<Vec<_>>>()?;
+        Some(Type::Fn(params, Box::new(self.return_type.clone()?)))
+    }
+}
+
+impl fmt::Display for Signature {
+    /// Formats like a function type, with `_` for unknown types
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let describe = |ty: &Option<Type>| ty.as_ref().map_or("_".to_string(), Type::to_string);
+        let params: Vec<_> = self.params.iter().map(describe).collect();
+        write!(f, "fn({}) -> {}", params.join(", "), describe(&self.return_type))
+    }
+}
+
+/// Result of analysing a program: its errors and the types worked out for it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    /// Errors found, top level first and then each function body
+    pub errors: Vec<SemanticError>,
+    /// Signature of every function, by name
+    pub signatures: BTreeMap<String, Signature>,
+    /// Static type of every expression, assignment and parameter whose type is known
+    types: Vec<(Span, Type)>,
+}
+
+impl Analysis {
+    /// Returns the type of the innermost expression, assignment or parameter
+    /// covering a byte offset in the source, e.g. for an editor hover
+    pub fn type_at(&self, offset: usize) -> Option<&Type> {
+        self.types
+            .iter()
+            .filter(|(span, _)| span.start <= offset && offset < span.end)
+            .min_by_key(|(span, _)| span.end - span.start)
+            .map(|(_, ty)| ty)
+    }
+
+    /// Returns the type recorded for exactly `span`, such as a whole
+    /// expression statement
+    pub fn type_of(&self, span: Span) -> Option<&Type> {
+        self.types.iter().rev().find(|(recorded, _)| *recorded == span).map(|(_, ty)| ty)
+    }
+}
+
+/// What the checker knows about a variable
+#[derive(Debug, Clone)]
+struct Binding {
+    /// Type fixed by the declaration or the first assignment
+    ty: Inferred,
+}
+
+/// A type being inferred from several uses, such as every `return` of a function
+#[derive(Debug, Clone, PartialEq)]
+enum Inferred {
+    /// No use seen yet, or only uses that depend on unfinished inference
+    Pending,
+    /// Every use so far has this type
+    Known(Type),
+    /// Uses disagree, or depend on values only known while running
+    Unknown,
+}
+
+impl Inferred {
+    /// Combines the types of two uses
+    fn join(&self, other: &Inferred) -> Inferred {
+        match (self, other) {
+            (Inferred::Pending, other) | (other, Inferred::Pending) => other.clone(),
+            (Inferred::Known(a), Inferred::Known(b)) if a == b => self.clone(),
+            _ => Inferred::Unknown,
+        }
+    }
+
+    fn known(&self) -> Option<Type> {
+        match self {
+            Inferred::Known(ty) => Some(ty.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Inferred types of a function's parameters and result
+#[derive(Debug, Clone, PartialEq)]
+struct FunctionTypes {
+    params: Vec<Inferred>,
+    result: Inferred,
+}
+
+/// Checks a whole program and infers the types in it
+///
+/// Each round checks the top-level statements and then every function body,
+/// so functions can use globals and other functions defined after them. A
+/// round uses the function types inferred by the one before, and rounds
+/// repeat until the inferred types stop changing, which resolves recursive
+/// functions.
+///
+/// # Arguments
+/// * `program` - Statements returned by `Parser::parse`
+///
+/// # Returns
+/// * The errors and inferred types of the final round
+pub fn analyze(program: &[Node]) -> Analysis {
+    let mut known = HashMap::new();
+    for round in 1.. {
+        let mut checker = Checker::new(known);
+        checker.run(program);
+        if checker.observed == checker.known || round == MAX_INFERENCE_ROUNDS {
+            return checker.into_analysis();
+        }
+        known = checker.observed;
+    }
+    unreachable!("the last inference round returns")
+}
+
+/// Walks the AST, tracking scopes and collecting errors
+///
+/// # Fields
+/// * `scopes` - Stack of variable scopes, innermost last; the first is the global scope
+/// * `frame_base` - Index into `scopes` of the function scope being checked, 0 at the top level
+/// * `functions` - Every function defined anywhere in the program, by name
+/// * `reached` - Functions whose `def` the top-level code has reached so far
+/// * `pending` - Function bodies still to be checked
+/// * `current_function` - Function whose body is being checked
+/// * `known` - Function types inferred by the previous round
+/// * `observed` - Function types seen so far in this round
+/// * `depends_on_pending` - Whether the expression being checked depends on a type that is still `Pending`
+/// * `types` - Static types found so far, by span
+/// * `errors` - Errors found so far
+struct Checker {
+    scopes: Vec<HashMap<String, Binding>>,
+    frame_base: usize,
+    functions: HashMap<String, Rc<Function>>,
+    reached: HashSet<String>,
+    pending: VecDeque<Rc<Function>>,
+    current_function: Option<Rc<Function>>,
+    known: HashMap<String, FunctionTypes>,
+    observed: HashMap<String, FunctionTypes>,
+    depends_on_pending: bool,
+    types: Vec<(Span, Type)>,
+    errors: Vec<SemanticError>,
+}
+
+impl Checker {
+    fn new(known: HashMap<String, FunctionTypes>) -> Self {
+        Checker {
+            scopes: vec![HashMap::new()],
+            frame_base: 0,
+            functions: HashMap::new(),
+            reached: HashSet::new(),
+            pending: VecDeque::new(),
+            current_function: None,
+            known,
+            observed: HashMap::new(),
+            depends_on_pending: false,
+            types: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Checks the top-level statements, then every function body
+    fn run(&mut self, program: &[Node]) {
+        for statement in program {
+            self.collect_functions(statement);
+        }
+        for statement in program {
+            self.statement(statement);
+        }
+        while let Some(function) = self.pending.pop_front() {
+            self.function_body(&function);
+        }
+    }
+
+    fn into_analysis(self) -> Analysis {
+        let signatures = self
+            .observed
+            .into_iter()
+            .map(|(name, types)| {
+                let signature = Signature {
+                    params: types.params.iter().map(Inferred::known).collect(),
+                    return_type: types.result.known(),
+                };
+                (name, signature)
+            })
+            .collect();
+        Analysis {
+            errors: self.errors,
+            signatures,
+            types: self.types,
+        }
+    }
+
+    /// Returns the types of a function as far as the previous round inferred them
+    fn known_types(&self, function: &Function) -> FunctionTypes {
+        self.known.get(&function.name).cloned().unwrap_or_else(|| declared_types(function))
+    }
+
+    /// Registers every function definition inside `node`
+    ///
+    /// The interpreter defines a function when execution reaches its `def`.
+    /// Top-level code can only call functions whose `def` comes before the
+    /// call, but a function body runs later and may call any of them.
+    fn collect_functions(&mut self, node: &Node) {
+        match &node.node {
+            ASTNode::FunctionDef(function) => {
+                self.functions.insert(function.name.clone(), Rc::clone(function));
+                self.observed.insert(function.name.clone(), declared_types(function));
+                self.pending.push_back(Rc::clone(function));
+                self.collect_functions(&function.body);
+            }
+            ASTNode::Block(statements) => {
+                for statement in statements {
+                    self.collect_functions(statement);
+                }
+            }
+            ASTNode::If(_, then_branch, else_branch) => {
+                self.collect_functions(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.collect_functions(else_branch);
+                }
+            }
+            ASTNode::While(_, body) | ASTNode::For(_, _, body) | ASTNode::OverflowBlock(_, body) => {
+                self.collect_functions(body)
+            }
+            _ => {}
+        }
+    }
+
+    /// Checks a function body in a scope holding its parameters
+    ///
+    /// Like a call in the interpreter, the body sees its own locals and the
+    /// global scope, but not the scopes it was defined in.
+    ///
+    /// Untyped parameters get the type every call passed them, if the calls agree.
+    fn function_body(&mut self, function: &Rc<Function>) {
+        let known = self.known_types(function);
+        let mut params = HashMap::new();
+        for (param, inferred) in function.params.iter().zip(&known.params) {
+            if let Inferred::Known(ty) = inferred {
+                self.types.push((param.span, ty.clone()));
+            }
+            params.insert(param.name.clone(), Binding { ty: inferred.clone() });
+        }
+        self.scopes.truncate(1);
+        self.scopes.push(params);
+        self.frame_base = 1;
+        self.current_function = Some(Rc::clone(function));
+        self.statement(&function.body);
+        // Falling off the end of the body returns `0`
+        if function.return_type.is_none() && !always_returns(&function.body) {
+            self.observe_result(function, Inferred::Known(Type::Int));
+        }
+        self.current_function = None;
+        self.frame_base = 0;
+        self.scopes.truncate(1);
+    }
+
+    /// Checks a statement
+    fn statement(&mut self, node: &Node) {
+        match &node.node {
+            ASTNode::Block(statements) => {
+                self.scopes.push(HashMap::new());
+                for statement in statements {
+                    self.statement(statement);
+                }
+                self.scopes.pop();
+            }
+            ASTNode::If(condition, then_branch, else_branch) => {
+                self.expression(condition);
+                self.statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            ASTNode::While(condition, body) => {
+                self.expression(condition);
+                self.statement(body);
+            }
+            ASTNode::For(name, iterable, body) => {
+                let item = match self.expression(iterable) {
+                    Some(Type::Range) => Some(Type::Int64),
+                    Some(ty) if ty.is_string() => Some(Type::Char),
+                    Some(ty) => {
+                        self.error(
+                            SemanticError::new(
+                                "E0207",
+                                format!("Cannot iterate over a value of type {}", ty),
+                                iterable.span,
+                            )
+                            .with_help("loop over a range, e.g. `range(0, n)`, or a string"),
+                        );
+                        None
+                    }
+                    None => None,
+                };
+                let binding = Binding {
+                    ty: item.map_or(Inferred::Unknown, Inferred::Known),
+                };
+                self.scopes.push(HashMap::from([(name.clone(), binding)]));
+                self.statement(body);
+                self.scopes.pop();
+            }
+            ASTNode::OverflowBlock(_, body) => self.statement(body),
+            ASTNode::Print(value) => {
+                self.expression(value);
+            }
+            ASTNode::TypedVarAssign(name, declared_type, value) => {
+                let ty = self.expression(value);
+                if declared_type.is_string() && ty.as_ref().is_some_and(Type::is_numeric) {
+                    self.error(
+                        SemanticError::new(
+                            "E0202",
+                            format!("Cannot assign numeric result to string variable '{}'", name),
+                            value.span,
+                        )
+                        .with_help("convert the value to a string or declare the variable as a number"),
+                    );
+                } else {
+                    self.check_assignable(declared_type, ty.as_ref(), value.span);
+                }
+                self.types.push((node.span, declared_type.clone()));
+                self.declare(name, Binding { ty: Inferred::Known(declared_type.clone()) });
+            }
+            ASTNode::VarAssign(name, value) => {
+                let ty = self.infer(value);
+                if let Some(ty) = self.assign(name, ty, value.span) {
+                    self.types.push((node.span, ty));
+                }
+            }
+            ASTNode::Return(value) => {
+                let inferred = match value {
+                    Some(value) => self.infer(value),
+                    // A bare `return` returns `0`
+                    None => Inferred::Known(Type::Int),
+                };
+                let Some(function) = self.current_function.clone() else {
+                    return;
+                };
+                match (&function.return_type, value) {
+                    (Some(return_type), Some(value)) => {
+                        self.check_assignable(return_type, inferred.known().as_ref(), value.span)
+                    }
+                    (Some(return_type), None) => self.error(SemanticError::new(
+                        "E0205",
+                        format!("Function '{}' must return a value of type {}", function.name, return_type),
+                        node.span,
+                    )),
+                    (None, _) => self.observe_result(&function, inferred),
+                }
+            }
+            // Registered by collect_functions and checked once the top level is done
+            ASTNode::FunctionDef(function) => {
+                self.reached.insert(function.name.clone());
+                let signature = self.known_types(function);
+                let signature = Signature {
+                    params: signature.params.iter().map(Inferred::known).collect(),
+                    return_type: signature.result.known(),
+                };
+                if let Some(ty) = signature.as_type() {
+                    self.types.push((node.span, ty));
+                }
+            }
+            ASTNode::Break | ASTNode::Continue | ASTNode::Error => {}
+            _ => {
+                self.expression(node);
+            }
+        }
+    }
+
+    /// Works out the static type of an expression, reporting any errors in it
+    ///
+    /// # Returns
+    /// * The type, or None if it is unknown or the expression has an error
+    fn expression(&mut self, node: &Node) -> Option<Type> {
+        let ty = self.expression_type(node);
+        if let Some(ty) = &ty {
+            self.types.push((node.span, ty.clone()));
+        }
+        ty
+    }
+
+    /// Works out the static type of an expression for inference, telling an
+    /// unknown type apart from one that depends on unfinished inference
+    fn infer(&mut self, node: &Node) -> Inferred {
+        let outer = std::mem::replace(&mut self.depends_on_pending, false);
+        let ty = self.expression(node);
+        let pending = self.depends_on_pending;
+        self.depends_on_pending |= outer;
+        match ty {
+            Some(ty) => Inferred::Known(ty),
+            None if pending => Inferred::Pending,
+            None => Inferred::Unknown,
+        }
+    }
+
+    fn expression_type(&mut self, node: &Node) -> Option<Type> {
+        match &node.node {
+            ASTNode::Number(_) => Some(Type::Int64),
+            ASTNode::Float(_) => Some(Type::Float64),
+            ASTNode::StringLiteral(_) => Some(Type::Str),
+            ASTNode::CharLiteral(_) => Some(Type::Char),
+            ASTNode::Bool(_) => Some(Type::Bool),
+            ASTNode::Identifier(name) => match self.lookup(name).map(|binding| binding.ty.clone()) {
+                Some(Inferred::Known(ty)) => Some(ty),
+                Some(Inferred::Pending) => {
+                    self.depends_on_pending = true;
+                    None
+                }
+                Some(Inferred::Unknown) => None,
+                None => {
+                    self.error(
+                        SemanticError::new("E0301", format!("Undefined variable: {}", name), node.span)
+                            .with_help(format!("assign a value to '{}' before using it", name)),
+                    );
+                    None
+                }
+            },
+            ASTNode::Call(name, arguments) => self.call(name, arguments, node.span),
+            ASTNode::BinaryOp(left, op, right) => {
+                let left_type = self.expression(left);
+                let right_type = self.expression(right);
+                let (left_type, right_type) = (left_type?, right_type?);
+                match ArithmeticOp::from_token(op) {
+                    Some(op) => {
+                        let result = left_type.arithmetic_result(op, &right_type);
+                        if result.is_none() {
+                            self.error(
+                                SemanticError::new(
+                                    "E0208",
+                                    format!(
+                                        "Unsupported operand types for {}: {} and {}",
+                                        op.symbol(),
+                                        left_type,
+                                        right_type
+                                    ),
+                                    node.span,
+                                )
+                                .with_label(left.span, format!("this is {}", left_type))
+                                .with_label(right.span, format!("this is {}", right_type)),
+                            );
+                        }
+                        result
+                    }
+                    None => {
+                        if left_type.common_type(&right_type).is_none() {
+                            self.error(SemanticError::new(
+                                "E0203",
+                                format!("Cannot compare {} with {}", left_type, right_type),
+                                node.span,
+                            ));
+                        }
+                        Some(Type::Bool)
+                    }
+                }
+            }
+            ASTNode::LogicalOp(left, _, right) => {
+                self.expression(left);
+                self.expression(right);
+                Some(Type::Bool)
+            }
+            ASTNode::UnaryOp(_, operand) => {
+                self.expression(operand);
+                Some(Type::Bool)
+            }
+            ASTNode::Cast(target, value) => {
+                if let Some(ty) = self.expression(value) {
+                    if !ty.can_cast_to(target) {
+                        self.error(
+                            SemanticError::new("E0209", format!("Cannot convert {} to {}", ty, target), node.span)
+                                .with_label(value.span, format!("this is {}", ty)),
+                        );
+                    }
+                }
+                Some(target.clone())
+            }
+            // The parser only puts expressions where a value is expected
+            _ => None,
+        }
+    }
+
+    /// Checks a call's arguments against the function's signature, and
+    /// records their types for inferring untyped parameters
+    ///
+    /// # Returns
+    /// * The declared or inferred return type, if known
+    fn call(&mut self, name: &str, arguments: &[Node], span: Span) -> Option<Type> {
+        let inferred: Vec<_> = arguments.iter().map(|argument| self.infer(argument)).collect();
+        let argument_types: Vec<_> = inferred.iter().map(Inferred::known).collect();
+        let defined_later = self.current_function.is_none() && !self.reached.contains(name);
+        let function = self.functions.get(name).cloned().filter(|_| !defined_later);
+        let Some(function) = function else {
+            if name == "range" {
+                return self.range(arguments, &argument_types, span);
+            }
+            let help = if self.functions.contains_key(name) {
+                format!("'{}' is defined further down; move its `def` above this call", name)
+            } else {
+                format!("define '{}' with `def` before calling it", name)
+            };
+            self.error(SemanticError::new("E0307", format!("Undefined function: {}", name), span).with_help(help));
+            return None;
+        };
+        if arguments.len() != function.params.len() {
+            self.error(SemanticError::new(
+                "E0204",
+                format!(
+                    "Function '{}' takes {} argument{} but {} were given",
+                    name,
+                    function.params.len(),
+                    if function.params.len() == 1 { "" } else { "s" },
+                    arguments.len()
+                ),
+                span,
+            ));
+        }
+        for (i, ((param, argument), ty)) in function.params.iter().zip(arguments).zip(&inferred).enumerate() {
+            match &param.param_type {
+                Some(param_type) => self.check_assignable(param_type, ty.known().as_ref(), argument.span),
+                None => {
+                    if let Some(types) = self.observed.get_mut(name) {
+                        types.params[i] = types.params[i].join(ty);
+                    }
+                }
+            }
+        }
+        match self.known_types(&function).result {
+            Inferred::Known(ty) => Some(ty),
+            Inferred::Pending => {
+                self.depends_on_pending = true;
+                None
+            }
+            Inferred::Unknown => None,
+        }
+    }
+
+    /// Records the type of one of the values a function returns
+    fn observe_result(&mut self, function: &Function, ty: Inferred) {
+        if let Some(types) = self.observed.get_mut(&function.name) {
+            types.result = types.result.join(&ty);
+        }
+    }
+
+    /// Checks a call to the built-in `range(start, end[, step])`
+    fn range(&mut self, arguments: &[Node], argument_types: &[Option<Type>], span: Span) -> Option<Type> {
+        if !(2..=3).contains(&arguments.len()) {
+            self.error(
+                SemanticError::new(
+                    "E0204",
+                    format!("Function 'range' takes 2 or 3 arguments but {} were given", arguments.len()),
+                    span,
+                )
+                .with_help("use `range(start, end)` or `range(start, end, step)`"),
+            );
+        }
+        for (argument, ty) in arguments.iter().zip(argument_types) {
+            if let Some(ty) = ty.as_ref().filter(|ty| !ty.is_integer()) {
+                self.error(SemanticError::new(
+                    "E0206",
+                    format!("range() arguments must be integers, found {}", ty),
+                    argument.span,
+                ));
+            }
+        }
+        Some(Type::Range)
+    }
+
+    /// Reports an error if a value of type `ty` can't be stored as `declared`
+    ///
+    /// Unknown types are assumed to fit; the interpreter checks them when the
+    /// program runs.
+    fn check_assignable(&mut self, declared: &Type, ty: Option<&Type>, span: Span) {
+        let Some(ty) = ty else {
+            return;
+        };
+        if !ty.promotes_to(declared) && !declared.is_compatible_with(ty) {
+            self.error(SemanticError::new(
+                "E0201",
+                format!("Type mismatch: Cannot assign {} to {}", ty, declared),
+                span,
+            ));
+        }
+    }
+
+    /// Looks up a variable in the current function's scopes and then the
+    /// global scope
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes[self.frame_base..]
+            .iter()
+            .rev()
+            .chain(self.scopes.first())
+            .find_map(|scope| scope.get(name))
+    }
+
+    /// Records an untyped assignment, mirroring the interpreter: the nearest
+    /// binding in the current function is updated, or a new one created
+    ///
+    /// The first assignment fixes the variable's type, and later values must
+    /// fit it.
+    ///
+    /// # Returns
+    /// * The variable's type, if known
+    fn assign(&mut self, name: &str, ty: Inferred, span: Span) -> Option<Type> {
+        let existing = (self.frame_base..self.scopes.len())
+            .rev()
+            .find(|&i| self.scopes[i].contains_key(name));
+        let Some(index) = existing else {
+            self.declare(name, Binding { ty: ty.clone() });
+            return ty.known();
+        };
+        let fixed = self.scopes[index][name].ty.known()?;
+        self.check_assignable(&fixed, ty.known().as_ref(), span);
+        Some(fixed)
+    }
+
+    /// Binds `name` in the innermost scope, shadowing any outer binding
+    fn declare(&mut self, name: &str, binding: Binding) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), binding);
+        }
+    }
+
+    fn error(&mut self, error: SemanticError) {
+        self.errors.push(error);
+    }
+}
+
+/// Returns the declared types of a function, with untyped parts still to be inferred
+fn declared_types(function: &Function) -> FunctionTypes {
+    let declared = |ty: &Option<Type>| ty.clone().map_or(Inferred::Pending, Inferred::Known);
+    FunctionTypes {
+        params: function.params.iter().map(|param| declared(&param.param_type)).collect(),
+        result: declared(&function.return_type),
+    }
+}
+
+/// Returns whether every path through a statement ends in `return`
+fn always_returns(node: &Node) -> bool {
+    match &node.node {
+        ASTNode::Return(_) => true,
+        ASTNode::Block(statements) => statements.iter().any(always_returns),
+        ASTNode::If(_, then_branch, Some(else_branch)) => always_returns(then_branch) && always_returns(else_branch),
+        ASTNode::OverflowBlock(_, body) => always_returns(body),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    /// Parses and analyses `input`
+    fn analyze_source(input: &str) -> Analysis {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        analyze(&parser.parse().unwrap())
+    }
+
+    /// Parses and checks `input`, returning the codes of the errors found
+    fn error_codes(input: &str) -> Vec<&'static str> {
+        analyze_source(input).errors.iter().map(|e| e.code).collect()
+    }
+
+    #[test]
+    fn test_valid_program() {
+        let input = "
+            def scale(x: int64, factor) -> float64 {
+                return float64(x * factor) * ratio
+            }
+            ratio = 0.5
+            total: int64 = 0
+            for i in range(0, 10) {
+                if i > 3 and i != 7 { total = total + i }
+            }
+            name: str8 = \"ruspy\"
+            label = name + '!'
+            initial: char = char(int32('r'))
+            for c in label { print c }
+            print scale(total, 2)
+            def early() { return later(1) }
+            def later(n) { return n }
+            print early()
+        ";
+        assert_eq!(error_codes(input), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_calls_before_def() {
+        // Top-level code runs in order, so `later` isn't defined yet at the first call
+        let input = "
+            print later(1)
+            def later(n) { return n }
+            print later(2)
+        ";
+        let errors = analyze_source(input).errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].code, errors[0].message.as_str()), ("E0307", "Undefined function: later"));
+        assert_eq!(errors[0].span.line, 2);
+        assert_eq!(
+            errors[0].help.as_deref(),
+            Some("'later' is defined further down; move its `def` above this call")
+        );
+    }
+
+    #[test]
+    fn test_type_errors() {
+        let cases = [
+            ("x: int64 = \"a\"", "E0201"),
+            ("x: str = 1", "E0202"),
+            ("x: int32 = 1\nx = 1.5", "E0201"),
+            ("x = 1 + 2.5", "E0208"),
+            ("x = \"a\" * \"b\"", "E0208"),
+            ("x = 1 < \"a\"", "E0203"),
+            ("x = int64(\"1\")", "E0209"),
+            ("x = char(1.5)", "E0209"),
+            ("c: char = \"a\"", "E0201"),
+            ("x = 'a' + 1", "E0208"),
+            ("x = 'a' * \"b\"", "E0208"),
+            ("x = 'a' == \"a\"", "E0203"),
+            ("for c in 5 { }", "E0207"),
+            ("for i in range(0, 1.5) { }", "E0206"),
+            ("def f(a: str) { return a } f(1)", "E0201"),
+            ("def f(a) { return a } f(1, 2)", "E0204"),
+            ("def f() -> int64 { return \"a\" }", "E0201"),
+            ("def f() -> int64 { return }", "E0205"),
+        ];
+        for (input, code) in cases {
+            assert_eq!(error_codes(input), vec![code], "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_undefined_names() {
+        let input = "
+            if true { inner = 1 }
+            print inner
+            missing(1)
+            def f() { return local }
+            def g() { local = 1 }
+        ";
+        assert_eq!(error_codes(input), vec!["E0301", "E0307", "E0301"]);
+    }
+
+    #[test]
+    fn test_first_assignment_fixes_type() {
+        assert_eq!(error_codes("x = 1\nx = \"one\""), vec!["E0201"]);
+        assert_eq!(error_codes("x = 1\nif true { x = 2 }\nx = int32(3)"), Vec::<&str>::new());
+        assert_eq!(error_codes("x = \"a\"\nfor i in range(0, 3) { x = i }"), vec!["E0201"]);
+    }
+
+    #[test]
+    fn test_unknown_types_are_not_checked() {
+        // `a` is passed both an int64 and a str, so its type is only known while running
+        let input = "
+            def f(a) { return a + \"s\" }
+            print f(\"t\")
+            z: int64 = f(1)
+        ";
+        assert_eq!(error_codes(input), Vec::<&str>::new());
+        let analysis = analyze_source(input);
+        assert_eq!(analysis.signatures["f"].to_string(), "fn(_) -> _");
+    }
+
+    #[test]
+    fn test_infers_signatures() {
+        let input = "
+            def fact(n) {
+                if n <= 1 { return 1 }
+                return n * fact(n - 1)
+            }
+            def greet(name) { return \"hi \" + name }
+            def nothing() { x = 1 }
+            print fact(5)
+            print greet(\"ruspy\")
+        ";
+        let analysis = analyze_source(input);
+        assert_eq!(analysis.errors, Vec::new());
+        assert_eq!(analysis.signatures["fact"].to_string(), "fn(int64) -> int64");
+        assert_eq!(analysis.signatures["greet"].to_string(), "fn(str) -> str");
+        assert_eq!(analysis.signatures["nothing"].to_string(), "fn() -> int");
+
+        // Inferred types are checked like declared ones
+        assert_eq!(error_codes(&format!("{}\ns: str = fact(3)", input)), vec!["E0202"]);
+        assert_eq!(error_codes(&format!("{}\nprint greet(\"a\") + 1", input)), vec!["E0208"]);
+    }
+
+    #[test]
+    fn test_type_at() {
+        let input = "def half(x) { return float64(x) / 2.0 }\ncount = 3\nprint half(count)";
+        let analysis = analyze_source(input);
+        let type_at = |text: &str| analysis.type_at(input.find(text).unwrap()).map(Type::to_string);
+        assert_eq!(type_at("half(x)"), Some("fn(int64) -> float64".to_string()));
+        assert_eq!(type_at("x)"), Some("int64".to_string()));
+        assert_eq!(type_at("2.0"), Some("float64".to_string()));
+        assert_eq!(type_at("count ="), Some("int64".to_string()));
+        assert_eq!(type_at("half(count)"), Some("float64".to_string()));
+        assert_eq!(type_at("print"), None);
+    }
+
+    #[test]
+    fn test_reports_all_errors() {
+        let input = "
+            a: int64 = \"a\"
+            b = a + true
+            c = undefined
+        ";
+        let errors = analyze_source(input).errors;
+        let codes: Vec<_> = errors.iter().map(|e| e.code).collect();
+        assert_eq!(codes, vec!["E0201", "E0208", "E0301"]);
+        assert_eq!(errors[1].message, "Unsupported operand types for +: int64 and bool");
+        assert_eq!(errors[1].span.line, 3);
+    }
+}
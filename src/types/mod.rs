@@ -1,5 +1,7 @@
+use crate::lexer::Token;
+use std::cmp::Ordering;
 use std::fmt;
-use std::ops::{Add, Sub, Mul, Div};
+use std::ops::{Add, Div, Mul, Sub};
 
 /// Represents the supported data types in Ruspy
 #[derive(Debug, PartialEq, Clone)]
@@ -11,130 +13,748 @@ pub enum RuspyType {
     Float32(f32),
     Float64(f64),
     Str(String),
+    /// Strings of at most 8, 32 and 64 bytes
+    Str8(FixedStr<8>),
+    Str32(FixedStr<32>),
+    Str64(FixedStr<64>),
     Char(char),
+    Bool(bool),
+    /// Half-open integer range: start, end (excluded) and a non-zero step
+    Range(i64, i64, i64),
 }
 
-impl RuspyType {
-    pub fn is_compatible_with(&self, other: &RuspyType) -> bool {
-        match (self, other) {
-            // Integers are compatible with each other
-            (RuspyType::Int(_), RuspyType::Int(_) | RuspyType::Int32(_) | RuspyType::Int64(_)) => true,
-            (RuspyType::Int32(_), RuspyType::Int(_) | RuspyType::Int32(_) | RuspyType::Int64(_)) => true,
-            (RuspyType::Int64(_), RuspyType::Int(_) | RuspyType::Int32(_) | RuspyType::Int64(_)) => true,
-            
-            // Floats are compatible with each other
-            (RuspyType::Float(_), RuspyType::Float(_) | RuspyType::Float32(_) | RuspyType::Float64(_)) => true,
-            (RuspyType::Float32(_), RuspyType::Float(_) | RuspyType::Float32(_) | RuspyType::Float64(_)) => true,
-            (RuspyType::Float64(_), RuspyType::Float(_) | RuspyType::Float32(_) | RuspyType::Float64(_)) => true,
-            
-            // Strings are only compatible with strings
-            (RuspyType::Str(_), RuspyType::Str(_)) => true,
-            
-            // Characters are only compatible with characters
-            (RuspyType::Char(_), RuspyType::Char(_)) => true,
-            
-            // Everything else is incompatible
+/// A string of at most `N` bytes, stored inline without a heap allocation
+///
+/// Backs the `str8`, `str32` and `str64` types. `N` must be below 256 so the
+/// length fits in a byte.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct FixedStr<const N: usize> {
+    len: u8,
+    bytes: [u8; N],
+}
+
+impl<const N: usize> FixedStr<N> {
+    /// Copies `text` into a new fixed string
+    ///
+    /// # Returns
+    /// * The fixed string, or None if `text` is longer than `N` bytes
+    pub fn new(text: &str) -> Option<Self> {
+        if text.len() > N {
+            return None;
+        }
+        let mut bytes = [0; N];
+        bytes[..text.len()].copy_from_slice(text.as_bytes());
+        Some(FixedStr {
+            len: text.len() as u8,
+            bytes,
+        })
+    }
+
+    /// Returns the contents as a string slice
+    pub fn as_str(&self) -> &str {
+        // The bytes are always copied from a whole `&str`, so they are valid UTF-8
+        std::str::from_utf8(&self.bytes[..usize::from(self.len)]).expect("FixedStr holds valid UTF-8")
+    }
+}
+
+impl<const N: usize> fmt::Debug for FixedStr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+/// A static type, as written in annotations and function signatures
+///
+/// Runtime values are [`RuspyType`]s; [`RuspyType::type_of`] gives the type
+/// of a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Int,
+    Int32,
+    Int64,
+    Float,
+    Float32,
+    Float64,
+    Str,
+    Str8,
+    Str32,
+    Str64,
+    Char,
+    Bool,
+    Range,
+    /// A function: parameter types and return type, as inferred for a `def`
+    Fn(Vec<Type>, Box<Type>),
+}
+
+impl Type {
+    /// Returns true for the integer types
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Type::Int | Type::Int32 | Type::Int64)
+    }
+
+    /// Returns true for the float types
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::Float | Type::Float32 | Type::Float64)
+    }
+
+    /// Returns true for the integer and float types
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+
+    /// Returns true for `str` and the fixed-capacity string types
+    pub fn is_string(&self) -> bool {
+        matches!(self, Type::Str | Type::Str8 | Type::Str32 | Type::Str64)
+    }
+
+    /// Returns the maximum length in bytes of a fixed-capacity string type
+    ///
+    /// # Returns
+    /// * The capacity, or None for `str` and non-string types
+    pub fn capacity(&self) -> Option<usize> {
+        match self {
+            Type::Str8 => Some(8),
+            Type::Str32 => Some(32),
+            Type::Str64 => Some(64),
+            _ => None,
+        }
+    }
+
+    /// Returns whether values of this type can be promoted to `target`
+    /// without losing information
+    ///
+    /// The promotion lattice is:
+    ///
+    /// ```text
+    /// int   -> int64, float64
+    /// int32 -> int64
+    /// float, float32 -> float64
+    /// str8 -> str32 -> str64 -> str
+    /// ```
+    ///
+    /// Every type also promotes to itself. Anything else, such as int64 to
+    /// float64, needs an explicit cast (see [`RuspyType::cast`]).
+    pub fn promotes_to(&self, target: &Type) -> bool {
+        match (self, target) {
+            _ if self == target => true,
+            (Type::Int | Type::Int32, Type::Int64) => true,
+            (Type::Int, Type::Float64) => true,
+            (Type::Float | Type::Float32, Type::Float64) => true,
+            // Only towards a larger (or unbounded) capacity
+            (from, to) if from.is_string() && to.is_string() => match (from.capacity(), to.capacity()) {
+                (_, None) => true,
+                (Some(from), Some(to)) => from <= to,
+                (None, Some(_)) => false,
+            },
             _ => false,
         }
     }
+
+    /// Returns the narrowest type both types promote to, if any
+    pub fn common_type(&self, other: &Type) -> Option<Type> {
+        // Candidates from narrowest to widest
+        let candidates = [self, other, &Type::Int64, &Type::Float64];
+        candidates
+            .into_iter()
+            .find(|target| self.promotes_to(target) && other.promotes_to(target))
+            .cloned()
+    }
+
+    /// Returns the type of `self op other`, or None if the operator doesn't
+    /// support these operand types
+    ///
+    /// The operands are promoted to their common type, which is also the
+    /// result type; strings support `+` only. A char can be added to either
+    /// end of a string, keeping the string's type.
+    pub fn arithmetic_result(&self, op: ArithmeticOp, other: &Type) -> Option<Type> {
+        match (self, other) {
+            (string, Type::Char) | (Type::Char, string) if string.is_string() => {
+                return (op == ArithmeticOp::Add).then(|| string.clone());
+            }
+            _ => {}
+        }
+        let common = self.common_type(other)?;
+        (common.is_numeric() || (common.is_string() && op == ArithmeticOp::Add)).then_some(common)
+    }
+
+    /// Returns whether an explicit cast such as `int32(x)` can convert values
+    /// of this type to `target`
+    ///
+    /// Numbers convert to other number types, strings to other string types,
+    /// and chars to and from their integer code points; the cast may still
+    /// fail at run time if the value doesn't fit.
+    pub fn can_cast_to(&self, target: &Type) -> bool {
+        (self.is_numeric() && target.is_numeric())
+            || (self.is_string() && target.is_string())
+            || (*self == Type::Char && target.is_integer())
+            || (self.is_integer() && *target == Type::Char)
+            || self == target
+    }
+
+    /// Returns whether a value of type `other` can be stored in a variable
+    /// of this type, possibly after a conversion
+    ///
+    /// Integer types accept each other, as do float types and string types;
+    /// every other type only accepts itself.
+    pub fn is_compatible_with(&self, other: &Type) -> bool {
+        (self.is_integer() && other.is_integer())
+            || (self.is_float() && other.is_float())
+            || (self.is_string() && other.is_string())
+            || self == other
+    }
 }
 
-impl fmt::Display for RuspyType {
+impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            RuspyType::Int(val) => write!(f, "{}", val),
-            RuspyType::Int32(val) => write!(f, "{}", val),
-            RuspyType::Int64(val) => write!(f, "{}", val),
-            RuspyType::Float(val) => write!(f, "{}", val),
-            RuspyType::Float32(val) => write!(f, "{}", val),
-            RuspyType::Float64(val) => write!(f, "{}", val),
-            RuspyType::Str(val) => write!(f, "{}", val),
-            RuspyType::Char(val) => write!(f, "{}", val),
+            Type::Int => write!(f, "int"),
+            Type::Int32 => write!(f, "int32"),
+            Type::Int64 => write!(f, "int64"),
+            Type::Float => write!(f, "float"),
+            Type::Float32 => write!(f, "float32"),
+            Type::Float64 => write!(f, "float64"),
+            Type::Str => write!(f, "str"),
+            Type::Str8 => write!(f, "str8"),
+            Type::Str32 => write!(f, "str32"),
+            Type::Str64 => write!(f, "str64"),
+            Type::Char => write!(f, "char"),
+            Type::Bool => write!(f, "bool"),
+            Type::Range => write!(f, "range"),
+            Type::Fn(params, ret) => {
+                let params: Vec<_> = params.iter().map(Type::to_string).collect();
+                write!(f, "fn({}) -> {}", params.join(", "), ret)
+            }
         }
     }
 }
 
-// Implement operator overloading for RuspyType
+/// Arithmetic operators that work on numeric values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithmeticOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
 
-impl Add for RuspyType {
-    type Output = Self;
-    
-    fn add(self, other: Self) -> Self {
-        match (self, other) {
-            (RuspyType::Int(a), RuspyType::Int(b)) => RuspyType::Int(a + b),
-            (RuspyType::Int32(a), RuspyType::Int32(b)) => RuspyType::Int32(a + b),
-            (RuspyType::Int64(a), RuspyType::Int64(b)) => RuspyType::Int64(a + b),
-            (RuspyType::Float(a), RuspyType::Float(b)) => RuspyType::Float(a + b),
-            (RuspyType::Float32(a), RuspyType::Float32(b)) => RuspyType::Float32(a + b),
-            (RuspyType::Float64(a), RuspyType::Float64(b)) => RuspyType::Float64(a + b),
-            (RuspyType::Str(a), RuspyType::Str(b)) => RuspyType::Str(format!("{}{}", a, b)),
-            _ => panic!("Incompatible types for addition")
+impl ArithmeticOp {
+    /// Returns the operation a binary operator token stands for, if any
+    pub fn from_token(token: &Token) -> Option<ArithmeticOp> {
+        match token {
+            Token::Plus => Some(ArithmeticOp::Add),
+            Token::Minus => Some(ArithmeticOp::Sub),
+            Token::Asterisk => Some(ArithmeticOp::Mul),
+            Token::Slash => Some(ArithmeticOp::Div),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the operation, as used in error messages
+    pub fn name(self) -> &'static str {
+        match self {
+            ArithmeticOp::Add => "addition",
+            ArithmeticOp::Sub => "subtraction",
+            ArithmeticOp::Mul => "multiplication",
+            ArithmeticOp::Div => "division",
+        }
+    }
+
+    /// Returns the operator as written in source code
+    pub fn symbol(self) -> &'static str {
+        match self {
+            ArithmeticOp::Add => "+",
+            ArithmeticOp::Sub => "-",
+            ArithmeticOp::Mul => "*",
+            ArithmeticOp::Div => "/",
         }
     }
 }
 
-impl Sub for RuspyType {
-    type Output = Self;
-    
-    fn sub(self, other: Self) -> Self {
-        match (self, other) {
-            (RuspyType::Int(a), RuspyType::Int(b)) => RuspyType::Int(a - b),
-            (RuspyType::Int32(a), RuspyType::Int32(b)) => RuspyType::Int32(a - b),
-            (RuspyType::Int64(a), RuspyType::Int64(b)) => RuspyType::Int64(a - b),
-            (RuspyType::Float(a), RuspyType::Float(b)) => RuspyType::Float(a - b),
-            (RuspyType::Float32(a), RuspyType::Float32(b)) => RuspyType::Float32(a - b),
-            (RuspyType::Float64(a), RuspyType::Float64(b)) => RuspyType::Float64(a - b),
-            _ => panic!("Incompatible types for subtraction")
+/// What integer arithmetic does when a result doesn't fit in its type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowMode {
+    /// Overflow is an error
+    #[default]
+    Checked,
+    /// Results wrap around at the type's bounds (two's complement)
+    Wrapping,
+    /// Results are clamped to the type's bounds
+    Saturating,
+}
+
+/// The ways an arithmetic operation can fail
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArithmeticError {
+    /// An integer result doesn't fit in its type, in checked mode
+    Overflow {
+        /// Name of the integer type, e.g. `int32`
+        type_name: &'static str,
+        op: ArithmeticOp,
+    },
+    /// A concatenated string doesn't fit in its fixed-capacity type
+    CapacityExceeded {
+        /// Name of the string type, e.g. `str8`
+        type_name: &'static str,
+        length: usize,
+        capacity: usize,
+    },
+    /// The right operand of a division is zero
+    DivisionByZero,
+    /// The operator isn't defined for these operand types
+    UnsupportedOperands {
+        op: ArithmeticOp,
+        left: &'static str,
+        right: &'static str,
+    },
+}
+
+impl fmt::Display for ArithmeticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArithmeticError::Overflow { type_name, op } => {
+                write!(f, "{} {} overflowed", type_name, op.name())
+            }
+            ArithmeticError::CapacityExceeded { type_name, length, capacity } => write!(
+                f,
+                "{} concatenation produced {} bytes, but {} holds at most {}",
+                type_name, length, type_name, capacity
+            ),
+            ArithmeticError::DivisionByZero => write!(f, "Division by zero"),
+            ArithmeticError::UnsupportedOperands { op, left, right } => write!(
+                f,
+                "Unsupported operand types for {}: {} and {}",
+                op.symbol(),
+                left,
+                right
+            ),
         }
     }
 }
 
-impl Mul for RuspyType {
-    type Output = Self;
-    
-    fn mul(self, other: Self) -> Self {
-        match (self, other) {
-            (RuspyType::Int(a), RuspyType::Int(b)) => RuspyType::Int(a * b),
-            (RuspyType::Int32(a), RuspyType::Int32(b)) => RuspyType::Int32(a * b),
-            (RuspyType::Int64(a), RuspyType::Int64(b)) => RuspyType::Int64(a * b),
-            (RuspyType::Float(a), RuspyType::Float(b)) => RuspyType::Float(a * b),
-            (RuspyType::Float32(a), RuspyType::Float32(b)) => RuspyType::Float32(a * b),
-            (RuspyType::Float64(a), RuspyType::Float64(b)) => RuspyType::Float64(a * b),
-            _ => panic!("Incompatible types for multiplication")
+/// The ways converting a value to another type can fail
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConversionError {
+    /// The value can't be represented in the target type
+    OutOfRange { value: String, target: Type },
+    /// A string is longer than the target type's capacity
+    CapacityExceeded { length: usize, target: Type },
+    /// There is no conversion between the two types
+    Unsupported { from: Type, to: Type },
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionError::OutOfRange { value, target } => {
+                write!(f, "Value {} is out of range for {}", value, target)
+            }
+            ConversionError::CapacityExceeded { length, target } => write!(
+                f,
+                "A string of {} bytes doesn't fit in {}, which holds at most {}",
+                length,
+                target,
+                target.capacity().unwrap_or_default()
+            ),
+            ConversionError::Unsupported { from, to } => write!(f, "Cannot convert {} to {}", from, to),
+        }
+    }
+}
+
+/// Integer arithmetic under each overflow mode, for the types backing the
+/// integer variants
+trait Integer: Sized {
+    /// Returns None when the result overflows in checked mode
+    fn apply(self, op: ArithmeticOp, other: Self, mode: OverflowMode) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {$(
+        impl Integer for $t {
+            fn apply(self, op: ArithmeticOp, other: Self, mode: OverflowMode) -> Option<Self> {
+                use ArithmeticOp::*;
+                use OverflowMode::*;
+                match (op, mode) {
+                    (Add, Checked) => self.checked_add(other),
+                    (Add, Wrapping) => Some(self.wrapping_add(other)),
+                    (Add, Saturating) => Some(self.saturating_add(other)),
+                    (Sub, Checked) => self.checked_sub(other),
+                    (Sub, Wrapping) => Some(self.wrapping_sub(other)),
+                    (Sub, Saturating) => Some(self.saturating_sub(other)),
+                    (Mul, Checked) => self.checked_mul(other),
+                    (Mul, Wrapping) => Some(self.wrapping_mul(other)),
+                    (Mul, Saturating) => Some(self.saturating_mul(other)),
+                    // Only MIN / -1 overflows
+                    (Div, Checked) => self.checked_div(other),
+                    (Div, Wrapping) => Some(self.wrapping_div(other)),
+                    (Div, Saturating) => Some(self.saturating_div(other)),
+                }
+            }
         }
+    )*};
+}
+
+impl_integer!(i32, i64);
+
+/// Applies an arithmetic operator to two floats of the same width
+fn float_op<T>(a: T, op: ArithmeticOp, b: T) -> T
+where
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T>,
+{
+    match op {
+        ArithmeticOp::Add => a + b,
+        ArithmeticOp::Sub => a - b,
+        ArithmeticOp::Mul => a * b,
+        ArithmeticOp::Div => a / b,
     }
 }
 
-impl Div for RuspyType {
-    type Output = Self;
-    
-    fn div(self, other: Self) -> Self {
+impl RuspyType {
+    /// Returns the static type of this value
+    pub fn type_of(&self) -> Type {
+        match self {
+            RuspyType::Int(_) => Type::Int,
+            RuspyType::Int32(_) => Type::Int32,
+            RuspyType::Int64(_) => Type::Int64,
+            RuspyType::Float(_) => Type::Float,
+            RuspyType::Float32(_) => Type::Float32,
+            RuspyType::Float64(_) => Type::Float64,
+            RuspyType::Str(_) => Type::Str,
+            RuspyType::Str8(_) => Type::Str8,
+            RuspyType::Str32(_) => Type::Str32,
+            RuspyType::Str64(_) => Type::Str64,
+            RuspyType::Char(_) => Type::Char,
+            RuspyType::Bool(_) => Type::Bool,
+            RuspyType::Range(..) => Type::Range,
+        }
+    }
+
+    /// Returns the Ruspy name of this value's type, as written in annotations
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            RuspyType::Int(_) => "int",
+            RuspyType::Int32(_) => "int32",
+            RuspyType::Int64(_) => "int64",
+            RuspyType::Float(_) => "float",
+            RuspyType::Float32(_) => "float32",
+            RuspyType::Float64(_) => "float64",
+            RuspyType::Str(_) => "str",
+            RuspyType::Str8(_) => "str8",
+            RuspyType::Str32(_) => "str32",
+            RuspyType::Str64(_) => "str64",
+            RuspyType::Char(_) => "char",
+            RuspyType::Bool(_) => "bool",
+            RuspyType::Range(..) => "range",
+        }
+    }
+
+    /// Returns whether this value counts as true in a condition
+    ///
+    /// Booleans are themselves; numbers are false when zero, strings when
+    /// empty, characters when `'\0'` and ranges when they produce no values.
+    /// NaN is true, as in Python.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            RuspyType::Int(n) | RuspyType::Int32(n) => *n != 0,
+            RuspyType::Int64(n) => *n != 0,
+            RuspyType::Float(n) | RuspyType::Float64(n) => *n != 0.0,
+            RuspyType::Float32(n) => *n != 0.0,
+            RuspyType::Str(_) | RuspyType::Str8(_) | RuspyType::Str32(_) | RuspyType::Str64(_) => {
+                self.as_str().is_some_and(|s| !s.is_empty())
+            }
+            RuspyType::Char(c) => *c != '\0',
+            RuspyType::Bool(b) => *b,
+            RuspyType::Range(..) => self.iterate().is_some_and(|mut items| items.next().is_some()),
+        }
+    }
+
+    /// Returns the values a `for` loop visits, or None if this value can't be
+    /// iterated over
+    ///
+    /// Ranges produce integers lazily; strings produce their characters.
+    pub fn iterate(&self) -> Option<Box<dyn Iterator<Item = RuspyType>>> {
+        match *self {
+            RuspyType::Range(start, end, step) => {
+                let values = std::iter::successors(Some(start), move |i| i.checked_add(step))
+                    .take_while(move |i| if step > 0 { *i < end } else { *i > end })
+                    .map(RuspyType::Int64);
+                Some(Box::new(values))
+            }
+            _ => {
+                let chars: Vec<char> = self.as_str()?.chars().collect();
+                Some(Box::new(chars.into_iter().map(RuspyType::Char)))
+            }
+        }
+    }
+
+    /// Returns the text of a string value of any capacity
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            RuspyType::Str(s) => Some(s),
+            RuspyType::Str8(s) => Some(s.as_str()),
+            RuspyType::Str32(s) => Some(s.as_str()),
+            RuspyType::Str64(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Stores `text` as a value of the string type `target`
+    ///
+    /// # Returns
+    /// * The value, or None if `text` doesn't fit in the type's capacity
+    fn string_of_type(target: &Type, text: &str) -> Option<RuspyType> {
+        match target {
+            Type::Str8 => FixedStr::new(text).map(RuspyType::Str8),
+            Type::Str32 => FixedStr::new(text).map(RuspyType::Str32),
+            Type::Str64 => FixedStr::new(text).map(RuspyType::Str64),
+            _ => Some(RuspyType::Str(text.to_string())),
+        }
+    }
+
+    /// Returns true for the integer and float variants
+    pub fn is_numeric(&self) -> bool {
+        self.type_of().is_numeric()
+    }
+
+    /// Converts this value to the type `target` if the promotion lattice
+    /// allows it (see [`Type::promotes_to`])
+    ///
+    /// # Returns
+    /// * The converted value, or None if the promotion isn't allowed
+    pub fn promote(&self, target: &Type) -> Option<RuspyType> {
+        if !self.type_of().promotes_to(target) {
+            return None;
+        }
+        match (self, target) {
+            (RuspyType::Int(n) | RuspyType::Int32(n), Type::Int64) => Some(RuspyType::Int64(i64::from(*n))),
+            (RuspyType::Int(n), Type::Float64) => Some(RuspyType::Float64(f64::from(*n))),
+            (RuspyType::Float(n), Type::Float64) => Some(RuspyType::Float64(*n)),
+            (RuspyType::Float32(n), Type::Float64) => Some(RuspyType::Float64(f64::from(*n))),
+            (value, target) if target.is_string() && value.type_of() != *target => {
+                RuspyType::string_of_type(target, value.as_str()?)
+            }
+            _ => Some(self.clone()),
+        }
+    }
+
+    /// Promotes two values to the narrowest type both can be promoted to
+    ///
+    /// # Returns
+    /// * Both values converted to their common type, or None if they have none
+    pub fn promote_pair(&self, other: &RuspyType) -> Option<(RuspyType, RuspyType)> {
+        let target = self.type_of().common_type(&other.type_of())?;
+        Some((self.promote(&target)?, other.promote(&target)?))
+    }
+
+    /// Converts this value to the type `target`, as written with an
+    /// explicit cast like `int32(x)`
+    ///
+    /// Conversions to integers truncate floats toward zero and fail if the
+    /// result doesn't fit. Conversions to floats may round, but fail if a
+    /// finite value would become infinite. Strings convert between string
+    /// types as long as they fit in the target's capacity. Chars convert to
+    /// their Unicode code point, and integers back to the char with that code
+    /// point if there is one.
+    ///
+    /// # Errors
+    /// * When the value doesn't fit in the target type
+    /// * When there is no conversion between the types
+    pub fn cast(&self, target: &Type) -> Result<RuspyType, ConversionError> {
+        if let Some(value) = self.promote(target) {
+            return Ok(value);
+        }
+        let out_of_range = || ConversionError::OutOfRange {
+            value: self.to_string(),
+            target: target.clone(),
+        };
+        let unsupported = ConversionError::Unsupported {
+            from: self.type_of(),
+            to: target.clone(),
+        };
+        match target {
+            Type::Int | Type::Int32 | Type::Int64 => {
+                let n = match (self.as_i64(), self.as_f64()) {
+                    (Some(n), _) => n,
+                    // i64::MAX as f64 rounds up to 2^63, which is out of range; NaN fails both checks
+                    (_, Some(f)) if f.trunc() >= i64::MIN as f64 && f.trunc() < i64::MAX as f64 => f.trunc() as i64,
+                    (_, Some(_)) => return Err(out_of_range()),
+                    _ => match self {
+                        RuspyType::Char(c) => i64::from(u32::from(*c)),
+                        _ => return Err(unsupported),
+                    },
+                };
+                match target {
+                    Type::Int => i32::try_from(n).map(RuspyType::Int).map_err(|_| out_of_range()),
+                    Type::Int32 => i32::try_from(n).map(RuspyType::Int32).map_err(|_| out_of_range()),
+                    _ => Ok(RuspyType::Int64(n)),
+                }
+            }
+            Type::Float | Type::Float32 | Type::Float64 => {
+                let f = match (self.as_i64(), self.as_f64()) {
+                    (Some(n), _) => n as f64,
+                    (_, Some(f)) => f,
+                    _ => return Err(unsupported),
+                };
+                match target {
+                    Type::Float32 if f.is_finite() && (f as f32).is_infinite() => Err(out_of_range()),
+                    Type::Float32 => Ok(RuspyType::Float32(f as f32)),
+                    Type::Float => Ok(RuspyType::Float(f)),
+                    _ => Ok(RuspyType::Float64(f)),
+                }
+            }
+            Type::Char => {
+                let n = self.as_i64().ok_or(unsupported)?;
+                u32::try_from(n)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .map(RuspyType::Char)
+                    .ok_or_else(out_of_range)
+            }
+            target if target.is_string() => {
+                let text = self.as_str().ok_or(unsupported)?;
+                RuspyType::string_of_type(target, text).ok_or(ConversionError::CapacityExceeded {
+                    length: text.len(),
+                    target: target.clone(),
+                })
+            }
+            _ => Err(unsupported),
+        }
+    }
+
+    /// Compares two values after promoting them to a common type
+    ///
+    /// # Returns
+    /// * The ordering of the two values, or None if they have no common
+    ///   type, can only be tested for equality (ranges) or a NaN makes them
+    ///   unordered
+    pub fn compare(&self, other: &RuspyType) -> Option<Ordering> {
+        let (left, right) = self.promote_pair(other)?;
+        if let (Some(a), Some(b)) = (left.as_str(), right.as_str()) {
+            return Some(a.cmp(b));
+        }
+        match (left, right) {
+            (RuspyType::Int(a), RuspyType::Int(b)) | (RuspyType::Int32(a), RuspyType::Int32(b)) => Some(a.cmp(&b)),
+            (RuspyType::Int64(a), RuspyType::Int64(b)) => Some(a.cmp(&b)),
+            (RuspyType::Float(a), RuspyType::Float(b)) | (RuspyType::Float64(a), RuspyType::Float64(b)) => {
+                a.partial_cmp(&b)
+            }
+            (RuspyType::Float32(a), RuspyType::Float32(b)) => a.partial_cmp(&b),
+            (RuspyType::Char(a), RuspyType::Char(b)) => Some(a.cmp(&b)),
+            (RuspyType::Bool(a), RuspyType::Bool(b)) => Some(a.cmp(&b)),
+            (a @ RuspyType::Range(..), b) => (a == b).then_some(Ordering::Equal),
+            _ => None,
+        }
+    }
+
+    /// Applies an arithmetic operator, handling integer overflow as `mode`
+    /// says
+    ///
+    /// The operands are first promoted to a common type (see
+    /// [`RuspyType::promote`]); strings support `+` only.
+    ///
+    /// # Errors
+    /// * When an integer result doesn't fit in its type in checked mode
+    /// * When dividing by zero
+    /// * When the operator isn't defined for the operand types
+    pub fn arithmetic(
+        &self,
+        op: ArithmeticOp,
+        other: &RuspyType,
+        mode: OverflowMode,
+    ) -> Result<RuspyType, ArithmeticError> {
+        let divisor_is_zero = other.as_i64() == Some(0) || other.as_f64() == Some(0.0);
+        if op == ArithmeticOp::Div && divisor_is_zero {
+            return Err(ArithmeticError::DivisionByZero);
+        }
+        let unsupported = ArithmeticError::UnsupportedOperands {
+            op,
+            left: self.type_name(),
+            right: other.type_name(),
+        };
         match (self, other) {
+            (string, RuspyType::Char(_)) | (RuspyType::Char(_), string) if string.as_str().is_some() => {
+                return if op == ArithmeticOp::Add {
+                    string.concat(&format!("{}{}", self, other))
+                } else {
+                    Err(unsupported)
+                };
+            }
+            _ => {}
+        }
+        let Some((left, right)) = self.promote_pair(other) else {
+            return Err(unsupported);
+        };
+        let overflow = || ArithmeticError::Overflow {
+            type_name: left.type_name(),
+            op,
+        };
+        match (&left, &right) {
             (RuspyType::Int(a), RuspyType::Int(b)) => {
-                if b == 0 { panic!("Division by zero"); }
-                RuspyType::Int(a / b)
-            },
+                a.apply(op, *b, mode).map(RuspyType::Int).ok_or_else(overflow)
+            }
             (RuspyType::Int32(a), RuspyType::Int32(b)) => {
-                if b == 0 { panic!("Division by zero"); }
-                RuspyType::Int32(a / b)
-            },
+                a.apply(op, *b, mode).map(RuspyType::Int32).ok_or_else(overflow)
+            }
             (RuspyType::Int64(a), RuspyType::Int64(b)) => {
-                if b == 0 { panic!("Division by zero"); }
-                RuspyType::Int64(a / b)
-            },
-            (RuspyType::Float(a), RuspyType::Float(b)) => {
-                if b == 0.0 { panic!("Division by zero"); }
-                RuspyType::Float(a / b)
-            },
-            (RuspyType::Float32(a), RuspyType::Float32(b)) => {
-                if b == 0.0 { panic!("Division by zero"); }
-                RuspyType::Float32(a / b)
-            },
-            (RuspyType::Float64(a), RuspyType::Float64(b)) => {
-                if b == 0.0 { panic!("Division by zero"); }
-                RuspyType::Float64(a / b)
-            },
-            _ => panic!("Incompatible types for division")
+                a.apply(op, *b, mode).map(RuspyType::Int64).ok_or_else(overflow)
+            }
+            (RuspyType::Float(a), RuspyType::Float(b)) => Ok(RuspyType::Float(float_op(*a, op, *b))),
+            (RuspyType::Float32(a), RuspyType::Float32(b)) => Ok(RuspyType::Float32(float_op(*a, op, *b))),
+            (RuspyType::Float64(a), RuspyType::Float64(b)) => Ok(RuspyType::Float64(float_op(*a, op, *b))),
+            // Both sides have the same string type after promotion
+            (a, b) if op == ArithmeticOp::Add && a.as_str().is_some() => a.concat(&format!("{}{}", a, b)),
+            _ => Err(unsupported),
+        }
+    }
+
+    /// Stores the result of a concatenation in this string value's type
+    ///
+    /// # Errors
+    /// * When `text` doesn't fit in the type's capacity
+    fn concat(&self, text: &str) -> Result<RuspyType, ArithmeticError> {
+        let target = self.type_of();
+        RuspyType::string_of_type(&target, text).ok_or(ArithmeticError::CapacityExceeded {
+            type_name: self.type_name(),
+            length: text.len(),
+            capacity: target.capacity().unwrap_or_default(),
+        })
+    }
+
+    /// Returns the value of an integer variant widened to i64
+    fn as_i64(&self) -> Option<i64> {
+        match self {
+            RuspyType::Int(n) | RuspyType::Int32(n) => Some(i64::from(*n)),
+            RuspyType::Int64(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a float variant widened to f64
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            RuspyType::Float(n) | RuspyType::Float64(n) => Some(*n),
+            RuspyType::Float32(n) => Some(f64::from(*n)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RuspyType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuspyType::Int(val) => write!(f, "{}", val),
+            RuspyType::Int32(val) => write!(f, "{}", val),
+            RuspyType::Int64(val) => write!(f, "{}", val),
+            RuspyType::Float(val) => write!(f, "{}", val),
+            RuspyType::Float32(val) => write!(f, "{}", val),
+            RuspyType::Float64(val) => write!(f, "{}", val),
+            RuspyType::Str(val) => write!(f, "{}", val),
+            RuspyType::Str8(val) => write!(f, "{}", val.as_str()),
+            RuspyType::Str32(val) => write!(f, "{}", val.as_str()),
+            RuspyType::Str64(val) => write!(f, "{}", val.as_str()),
+            RuspyType::Char(val) => write!(f, "{}", val),
+            RuspyType::Bool(val) => write!(f, "{}", val),
+            RuspyType::Range(start, end, 1) => write!(f, "range({}, {})", start, end),
+            RuspyType::Range(start, end, step) => write!(f, "range({}, {}, {})", start, end, step),
         }
     }
 }
@@ -158,6 +778,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_type_inference() {
         assert_eq!(infer_type("42"), RuspyType::Int(42));
         assert_eq!(infer_type("3.14"), RuspyType::Float(3.14));
@@ -165,39 +786,258 @@ mod tests {
         assert_eq!(infer_type("hello"), RuspyType::Str("hello".to_string()));
     }
 
+    #[test]
+    fn test_type_name() {
+        assert_eq!(RuspyType::Int32(1).type_name(), "int32");
+        assert_eq!(RuspyType::Float64(1.0).type_name(), "float64");
+        assert_eq!(RuspyType::Str(String::new()).type_name(), "str");
+    }
+
+    #[test]
+    fn test_truthiness() {
+        assert!(RuspyType::Int64(-1).is_truthy());
+        assert!(!RuspyType::Int32(0).is_truthy());
+        assert!(!RuspyType::Float(0.0).is_truthy());
+        assert!(RuspyType::Float64(f64::NAN).is_truthy());
+        assert!(!RuspyType::Str(String::new()).is_truthy());
+        assert!(RuspyType::Str("0".to_string()).is_truthy());
+        assert!(!RuspyType::Char('\0').is_truthy());
+        assert!(RuspyType::Char('a').is_truthy());
+    }
+
+    #[test]
+    fn test_iterate() {
+        let collect = |value: RuspyType| value.iterate().map(|items| items.collect::<Vec<_>>());
+        let ints = |values: &[i64]| Some(values.iter().map(|n| RuspyType::Int64(*n)).collect());
+        assert_eq!(collect(RuspyType::Range(0, 4, 1)), ints(&[0, 1, 2, 3]));
+        assert_eq!(collect(RuspyType::Range(10, 0, -4)), ints(&[10, 6, 2]));
+        assert_eq!(collect(RuspyType::Range(3, 3, 1)), ints(&[]));
+        assert_eq!(collect(RuspyType::Range(i64::MAX - 1, i64::MAX, 5)), ints(&[i64::MAX - 1]));
+        assert_eq!(
+            collect(RuspyType::Str("hé".to_string())),
+            Some(vec![RuspyType::Char('h'), RuspyType::Char('é')])
+        );
+        assert_eq!(collect(RuspyType::Int(3)), None);
+        assert!(!RuspyType::Range(5, 0, 1).is_truthy());
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(RuspyType::Int(3).compare(&RuspyType::Int64(5)), Some(Ordering::Less));
+        assert_eq!(RuspyType::Float32(2.5).compare(&RuspyType::Float64(2.5)), Some(Ordering::Equal));
+        assert_eq!(RuspyType::Float(f64::NAN).compare(&RuspyType::Float(1.0)), None);
+        assert_eq!(
+            RuspyType::Str("b".to_string()).compare(&RuspyType::Str("a".to_string())),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(RuspyType::Bool(false).compare(&RuspyType::Bool(true)), Some(Ordering::Less));
+        assert_eq!(RuspyType::Int(1).compare(&RuspyType::Float(1.0)), Some(Ordering::Equal));
+        assert_eq!(RuspyType::Int64(1).compare(&RuspyType::Float(1.0)), None);
+    }
+
+    #[test]
+    fn test_promotion() {
+        assert_eq!(RuspyType::Int(5).promote(&Type::Int64), Some(RuspyType::Int64(5)));
+        assert_eq!(RuspyType::Int(5).promote(&Type::Float64), Some(RuspyType::Float64(5.0)));
+        assert_eq!(RuspyType::Float32(0.5).promote(&Type::Float64), Some(RuspyType::Float64(0.5)));
+        assert_eq!(RuspyType::Int64(5).promote(&Type::Int32), None);
+        assert_eq!(RuspyType::Int64(5).promote(&Type::Float64), None);
+
+        assert_eq!(
+            RuspyType::Int(2).promote_pair(&RuspyType::Int32(3)),
+            Some((RuspyType::Int64(2), RuspyType::Int64(3)))
+        );
+        assert_eq!(
+            RuspyType::Float32(1.5).promote_pair(&RuspyType::Int(2)),
+            Some((RuspyType::Float64(1.5), RuspyType::Float64(2.0)))
+        );
+        assert_eq!(RuspyType::Int32(1).promote_pair(&RuspyType::Float64(1.0)), None);
+        assert_eq!(
+            RuspyType::Int(5).arithmetic(ArithmeticOp::Add, &RuspyType::Int64(3), OverflowMode::Checked),
+            Ok(RuspyType::Int64(8))
+        );
+    }
+
+    #[test]
+    fn test_cast() {
+        assert_eq!(RuspyType::Float64(-2.9).cast(&Type::Int32), Ok(RuspyType::Int32(-2)));
+        assert_eq!(RuspyType::Int64(7).cast(&Type::Float), Ok(RuspyType::Float(7.0)));
+        assert!(matches!(
+            RuspyType::Float64(1e300).cast(&Type::Float32),
+            Err(ConversionError::OutOfRange { target: Type::Float32, .. })
+        ));
+        assert_eq!(
+            RuspyType::Int64(3_000_000_000).cast(&Type::Int32),
+            Err(ConversionError::OutOfRange { value: "3000000000".to_string(), target: Type::Int32 })
+        );
+        assert!(RuspyType::Float64(f64::NAN).cast(&Type::Int64).is_err());
+        assert!(RuspyType::Float64(9.3e18).cast(&Type::Int64).is_err());
+        assert_eq!(
+            RuspyType::Str("1".to_string()).cast(&Type::Int),
+            Err(ConversionError::Unsupported { from: Type::Str, to: Type::Int })
+        );
+    }
+
     #[test]
     fn test_type_compatibility() {
-        let int_type = RuspyType::Int(0);
-        let int64_type = RuspyType::Int64(0);
-        let float_type = RuspyType::Float(0.0);
+        assert!(Type::Int.is_compatible_with(&Type::Int64));
+        assert!(Type::Float32.is_compatible_with(&Type::Float));
+        assert!(!Type::Int.is_compatible_with(&Type::Float));
+        assert!(!Type::Str.is_compatible_with(&Type::Char));
+    }
+
+    #[test]
+    fn test_fixed_strings() {
+        let text = FixedStr::<8>::new("héllo").unwrap();
+        assert_eq!(text.as_str(), "héllo");
+        assert_eq!(FixedStr::<8>::new("too long!"), None);
+        // Stored inline: the buffer plus a length byte
+        assert_eq!(std::mem::size_of::<FixedStr<8>>(), 9);
+
+        let str8 = |s| RuspyType::Str8(FixedStr::new(s).unwrap());
+        assert_eq!(str8("ab").promote(&Type::Str32), Some(RuspyType::Str32(FixedStr::new("ab").unwrap())));
+        assert_eq!(str8("ab").promote(&Type::Str), Some(RuspyType::Str("ab".to_string())));
+        assert_eq!(RuspyType::Str("ab".to_string()).promote(&Type::Str8), None);
+        assert_eq!(RuspyType::Str("ab".to_string()).cast(&Type::Str8), Ok(str8("ab")));
+        assert_eq!(
+            RuspyType::Str("abcdefghi".to_string()).cast(&Type::Str8),
+            Err(ConversionError::CapacityExceeded { length: 9, target: Type::Str8 })
+        );
 
-        assert!(int_type.is_compatible_with(&int64_type));
-        assert!(!int_type.is_compatible_with(&float_type));
+        let add = |a: RuspyType, b: RuspyType| a.arithmetic(ArithmeticOp::Add, &b, OverflowMode::Checked);
+        assert_eq!(add(str8("abcd"), str8("efgh")), Ok(str8("abcdefgh")));
+        assert_eq!(
+            add(str8("abcde"), str8("fgh!")).unwrap_err().to_string(),
+            "str8 concatenation produced 9 bytes, but str8 holds at most 8"
+        );
+        assert_eq!(add(str8("ab"), RuspyType::Str("c".to_string())), Ok(RuspyType::Str("abc".to_string())));
+        assert_eq!(str8("b").compare(&RuspyType::Str("a".to_string())), Some(Ordering::Greater));
+        assert!(!str8("").is_truthy());
+    }
+
+    #[test]
+    fn test_chars() {
+        assert_eq!(RuspyType::Char('a').compare(&RuspyType::Char('b')), Some(Ordering::Less));
+        assert_eq!(RuspyType::Char('a').compare(&RuspyType::Int64(97)), None);
+
+        assert_eq!(RuspyType::Char('a').cast(&Type::Int64), Ok(RuspyType::Int64(97)));
+        assert_eq!(RuspyType::Char('😀').cast(&Type::Int32), Ok(RuspyType::Int32(0x1F600)));
+        assert_eq!(RuspyType::Int(233).cast(&Type::Char), Ok(RuspyType::Char('é')));
+        assert_eq!(
+            RuspyType::Int64(0xD800).cast(&Type::Char),
+            Err(ConversionError::OutOfRange { value: "55296".to_string(), target: Type::Char })
+        );
+        assert!(RuspyType::Int64(-1).cast(&Type::Char).is_err());
+        assert!(RuspyType::Float64(97.0).cast(&Type::Char).is_err());
+        assert!(Type::Char.can_cast_to(&Type::Int32) && Type::Int64.can_cast_to(&Type::Char));
+        assert!(!Type::Char.can_cast_to(&Type::Float64) && !Type::Str.can_cast_to(&Type::Char));
+
+        let add = |a: RuspyType, b: RuspyType| a.arithmetic(ArithmeticOp::Add, &b, OverflowMode::Checked);
+        assert_eq!(
+            add(RuspyType::Str("ab".to_string()), RuspyType::Char('c')),
+            Ok(RuspyType::Str("abc".to_string()))
+        );
+        assert_eq!(
+            add(RuspyType::Char('>'), RuspyType::Str8(FixedStr::new("ab").unwrap())),
+            Ok(RuspyType::Str8(FixedStr::new(">ab").unwrap()))
+        );
+        assert!(matches!(
+            add(RuspyType::Str8(FixedStr::new("12345678").unwrap()), RuspyType::Char('9')),
+            Err(ArithmeticError::CapacityExceeded { length: 9, .. })
+        ));
+        assert!(add(RuspyType::Char('a'), RuspyType::Char('b')).is_err());
+        assert!(RuspyType::Str(String::new())
+            .arithmetic(ArithmeticOp::Mul, &RuspyType::Char('a'), OverflowMode::Checked)
+            .is_err());
+        assert_eq!(Type::Str32.arithmetic_result(ArithmeticOp::Add, &Type::Char), Some(Type::Str32));
+        assert_eq!(Type::Char.arithmetic_result(ArithmeticOp::Add, &Type::Str), Some(Type::Str));
+        assert_eq!(Type::Char.arithmetic_result(ArithmeticOp::Add, &Type::Char), None);
+    }
+
+    #[test]
+    fn test_type_of() {
+        assert_eq!(RuspyType::Int32(1).type_of(), Type::Int32);
+        assert_eq!(RuspyType::Range(0, 3, 1).type_of(), Type::Range);
+        assert_eq!(Type::Float64.to_string(), "float64");
+        assert_eq!(
+            Type::Fn(vec![Type::Int64, Type::Bool], Box::new(Type::Str)).to_string(),
+            "fn(int64, bool) -> str"
+        );
     }
 
     #[test]
     fn test_arithmetic_operations() {
         let a = RuspyType::Int(5);
         let b = RuspyType::Int(3);
+        let apply = |op| a.arithmetic(op, &b, OverflowMode::Checked);
 
-        assert_eq!(a.clone() + b.clone(), RuspyType::Int(8));
-        assert_eq!(a.clone() - b.clone(), RuspyType::Int(2));
-        assert_eq!(a.clone() * b.clone(), RuspyType::Int(15));
-        assert_eq!(a.clone() / b.clone(), RuspyType::Int(1));
+        assert_eq!(apply(ArithmeticOp::Add), Ok(RuspyType::Int(8)));
+        assert_eq!(apply(ArithmeticOp::Sub), Ok(RuspyType::Int(2)));
+        assert_eq!(apply(ArithmeticOp::Mul), Ok(RuspyType::Int(15)));
+        assert_eq!(apply(ArithmeticOp::Div), Ok(RuspyType::Int(1)));
     }
 
     #[test]
-    #[should_panic(expected = "Division by zero")]
     fn test_division_by_zero() {
-        let a = RuspyType::Int(5);
-        let b = RuspyType::Int(0);
-        let _ = a / b;
+        let cases = [
+            (RuspyType::Int(5), RuspyType::Int(0)),
+            (RuspyType::Int64(5), RuspyType::Int64(0)),
+            (RuspyType::Float32(5.0), RuspyType::Float32(0.0)),
+        ];
+        for (a, b) in cases {
+            assert_eq!(
+                a.arithmetic(ArithmeticOp::Div, &b, OverflowMode::Wrapping),
+                Err(ArithmeticError::DivisionByZero)
+            );
+        }
+    }
+
+    #[test]
+    fn test_unsupported_operands() {
+        let error = RuspyType::Int64(1)
+            .arithmetic(ArithmeticOp::Add, &RuspyType::Float64(1.0), OverflowMode::Checked)
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Unsupported operand types for +: int64 and float64");
+        let error = RuspyType::Str("a".to_string())
+            .arithmetic(ArithmeticOp::Mul, &RuspyType::Str("b".to_string()), OverflowMode::Checked)
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Unsupported operand types for *: str and str");
+    }
+
+    #[test]
+    fn test_overflow_modes() {
+        use ArithmeticOp::*;
+        use OverflowMode::*;
+        let max32 = RuspyType::Int32(i32::MAX);
+        let one32 = RuspyType::Int32(1);
+        assert_eq!(
+            max32.arithmetic(Add, &one32, Checked),
+            Err(ArithmeticError::Overflow { type_name: "int32", op: Add })
+        );
+        assert_eq!(max32.arithmetic(Add, &one32, Wrapping), Ok(RuspyType::Int32(i32::MIN)));
+        assert_eq!(max32.arithmetic(Add, &one32, Saturating), Ok(max32.clone()));
+
+        let min64 = RuspyType::Int64(i64::MIN);
+        let minus_one = RuspyType::Int64(-1);
+        assert_eq!(
+            min64.arithmetic(Div, &minus_one, Checked).unwrap_err().to_string(),
+            "int64 division overflowed"
+        );
+        assert_eq!(min64.arithmetic(Mul, &minus_one, Saturating), Ok(RuspyType::Int64(i64::MAX)));
+        assert_eq!(min64.arithmetic(Sub, &RuspyType::Int64(1), Wrapping), Ok(RuspyType::Int64(i64::MAX)));
+        assert_eq!(
+            RuspyType::Float(1.5).arithmetic(Mul, &RuspyType::Float(2.0), Checked),
+            Ok(RuspyType::Float(3.0))
+        );
     }
 
     #[test]
     fn test_string_concatenation() {
         let a = RuspyType::Str("Hello, ".to_string());
         let b = RuspyType::Str("World!".to_string());
-        assert_eq!(a + b, RuspyType::Str("Hello, World!".to_string()));
+        assert_eq!(
+            a.arithmetic(ArithmeticOp::Add, &b, OverflowMode::Checked),
+            Ok(RuspyType::Str("Hello, World!".to_string()))
+        );
     }
 }